@@ -0,0 +1,163 @@
+use cell_game::{
+    cells::{cell::Cell, food_cell::FoodCell},
+    client_connection::{ClientConnection, PlayerInput},
+    ids::{FoodCellId, Id},
+    pos::{Circle, Point, Rect, Vec2},
+    protocol::{delta::ViewEncoder, messages::ServerMessage},
+    server::{game_config::GameConfig, game_server::GameServer, server_view::ServerView},
+    spatial_index::SpatialIndex,
+};
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+const FOOD: usize = 10_000;
+const PLAYERS: usize = 200;
+const BOUNDS: Rect = Rect {
+    top_left: Point { x: 0.0, y: 0.0 },
+    width: 8000.0,
+    height: 8000.0,
+};
+const TILE_SIZE: f64 = 128.0;
+
+fn random_point(rng: &mut StdRng) -> Point {
+    Point {
+        x: rng.gen::<f64>() * BOUNDS.width,
+        y: rng.gen::<f64>() * BOUNDS.height,
+    }
+}
+
+/// Hitboxes of player cells between the spawn mass and a mass of 500
+fn player_hitboxes(rng: &mut StdRng) -> Vec<Circle> {
+    (0..PLAYERS)
+        .map(|_| Circle {
+            center: random_point(rng),
+            radius: (rng.gen_range(20.0..500.0) * FoodCell::MASS_AREA_MULTIPLIER).sqrt(),
+        })
+        .collect()
+}
+
+fn food(rng: &mut StdRng) -> Vec<FoodCell> {
+    (0..FOOD)
+        .map(|i| {
+            let pos = random_point(rng);
+            FoodCell::new(FoodCellId::new(i as u32), pos, rng)
+        })
+        .collect()
+}
+
+fn bench_queries(c: &mut Criterion) {
+    let mut rng = StdRng::seed_from_u64(0);
+    let players = player_hitboxes(&mut rng);
+    let food = food(&mut rng);
+    let views: Vec<Circle> = players.iter().map(|p| p.scale_centered(30.0)).collect();
+
+    let mut group = c.benchmark_group("eat_food");
+    group.bench_function("naive", |b| {
+        b.iter(|| {
+            players
+                .iter()
+                .map(|hitbox| {
+                    food.iter()
+                        .filter(|f| hitbox.contains_point(f.pos()))
+                        .count()
+                })
+                .sum::<usize>()
+        })
+    });
+    group.bench_function("indexed", |b| {
+        b.iter(|| {
+            let index = SpatialIndex::from_cells(BOUNDS, TILE_SIZE, &food);
+            players
+                .iter()
+                .map(|hitbox| {
+                    index
+                        .near(*hitbox)
+                        .filter(|&i| hitbox.contains_point(food[i].pos()))
+                        .count()
+                })
+                .sum::<usize>()
+        })
+    });
+    group.finish();
+
+    let mut group = c.benchmark_group("visibility");
+    group.bench_function("naive", |b| {
+        b.iter(|| {
+            views
+                .iter()
+                .map(|view| {
+                    food.iter()
+                        .filter(|f| f.hitbox().overlaps_circle(*view))
+                        .count()
+                })
+                .sum::<usize>()
+        })
+    });
+    group.bench_function("indexed", |b| {
+        b.iter(|| {
+            let index = SpatialIndex::from_cells(BOUNDS, TILE_SIZE, &food);
+            views
+                .iter()
+                .map(|view| {
+                    index
+                        .near(*view)
+                        .filter(|&i| food[i].hitbox().overlaps_circle(*view))
+                        .count()
+                })
+                .sum::<usize>()
+        })
+    });
+    group.finish();
+}
+
+/// Wanders in a fixed direction and sends every view as a delta, like a
+/// socket connection does
+struct Wanderer {
+    move_vec: Vec2,
+    encoder: ViewEncoder,
+}
+
+impl<'a> ClientConnection<'a> for Wanderer {
+    type V = ServerView<'a>;
+
+    fn on_tick(&mut self, view: Self::V) -> PlayerInput {
+        let delta = self.encoder.encode(&view);
+        // Every view arrives, so each delta is against the one before it
+        self.encoder.acknowledge(delta.frame);
+        black_box(ServerMessage::View(Box::new(delta)).encode());
+        PlayerInput {
+            move_vec: self.move_vec,
+            ..Default::default()
+        }
+    }
+}
+
+fn bench_tick(c: &mut Criterion) {
+    let mut rng = StdRng::seed_from_u64(0);
+    let mut game = GameServer::with_seed(
+        GameConfig {
+            width: BOUNDS.width,
+            height: BOUNDS.height,
+            food_amount: FOOD,
+            ..GameConfig::default()
+        },
+        0,
+    );
+    for i in 0..PLAYERS {
+        let angle = rng.gen::<f64>() * std::f64::consts::TAU;
+        let dir = Vec2 {
+            x: angle.cos() * 100.0,
+            y: angle.sin() * 100.0,
+        };
+        let wanderer = Wanderer {
+            move_vec: dir,
+            encoder: ViewEncoder::new(),
+        };
+        game.connect_player(format!("Bot {}", i), Box::new(wanderer));
+    }
+
+    c.bench_function("tick_10k_food_200_players", |b| b.iter(|| game.tick()));
+}
+
+criterion_group!(benches, bench_queries, bench_tick);
+criterion_main!(benches);
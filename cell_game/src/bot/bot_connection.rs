@@ -0,0 +1,118 @@
+use rand::{rngs::StdRng, SeedableRng};
+
+use crate::{
+    client_connection::{ClientConnection, PlayerInput},
+    game_view::GameView,
+    server::server_view::ServerView,
+};
+
+use super::{bot_view::BotView, difficulty::Difficulty, strategy::Strategy};
+
+/// A computer controlled player. Bots only connect to a `GameServer`, so
+/// `ClientConnection` is implemented for `ServerView` alone: the server takes
+/// connections whose view type depends on the tick's borrow, which a bot
+/// generic over its view type can't express. Bots still play with any other
+/// `GameView` by being asked for input through [`BotConnection::decide`].
+pub struct BotConnection {
+    /// Asked in order until one of them returns an input
+    strategies: Vec<Box<dyn Strategy>>,
+    reaction_ticks: u32,
+    ticks_until_decision: u32,
+    last_input: PlayerInput,
+    rng: StdRng,
+}
+
+impl BotConnection {
+    pub fn new(difficulty: Difficulty, seed: u64) -> Self {
+        Self::with_strategies(difficulty.strategies(), difficulty.reaction_ticks(), seed)
+    }
+
+    /// Creates a bot with a custom set of strategies, given from highest to
+    /// lowest priority. The bot makes a new decision every `reaction_ticks`
+    /// ticks.
+    pub fn with_strategies(
+        strategies: Vec<Box<dyn Strategy>>,
+        reaction_ticks: u32,
+        seed: u64,
+    ) -> Self {
+        Self {
+            strategies,
+            reaction_ticks: reaction_ticks.max(1),
+            ticks_until_decision: 0,
+            last_input: PlayerInput::default(),
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+
+    /// Returns the bot's input for the next tick. Between decisions the bot
+    /// keeps moving the way it last decided to, without splitting or
+    /// ejecting again.
+    pub fn decide(&mut self, view: &impl GameView) -> PlayerInput {
+        if self.ticks_until_decision > 0 {
+            self.ticks_until_decision -= 1;
+            return PlayerInput {
+                split: false,
+                eject: false,
+                ..self.last_input.clone()
+            };
+        }
+        self.ticks_until_decision = self.reaction_ticks - 1;
+
+        let view = BotView::new(view);
+        let rng = &mut self.rng;
+        if let Some(input) = self
+            .strategies
+            .iter_mut()
+            .find_map(|strategy| strategy.decide(&view, rng))
+        {
+            self.last_input = input;
+        }
+        self.last_input.clone()
+    }
+}
+
+/// Only for `ServerView`, see [`BotConnection`]. Other views call
+/// [`BotConnection::decide`] directly.
+impl<'a> ClientConnection<'a> for BotConnection {
+    type V = ServerView<'a>;
+
+    fn on_tick(&mut self, view: Self::V) -> PlayerInput {
+        self.decide(&view)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        protocol::messages::ViewFrame, server::game_server::GameServer, test_support::small_config,
+    };
+
+    use super::*;
+
+    fn assert_same_input(a: &PlayerInput, b: &PlayerInput) {
+        assert_eq!(a.move_vec.x.to_bits(), b.move_vec.x.to_bits());
+        assert_eq!(a.move_vec.y.to_bits(), b.move_vec.y.to_bits());
+        assert_eq!(a.split, b.split);
+        assert_eq!(a.eject, b.eject);
+        assert_eq!(a.freeze, b.freeze);
+    }
+
+    #[test]
+    fn bots_decide_the_same_from_any_view_of_the_game() {
+        let mut game = GameServer::with_seed(small_config(), 3);
+        let player = game.connect_bot("Watched".into(), Difficulty::Hard);
+        for _ in 0..4 {
+            game.connect_bot("Bot".into(), Difficulty::Hard);
+        }
+
+        let mut on_server = BotConnection::new(Difficulty::Hard, 9);
+        let mut on_frame = BotConnection::new(Difficulty::Hard, 9);
+        for _ in 0..100 {
+            game.tick();
+            game.respawn_dead_players();
+            let view = game.player_view(player).unwrap();
+            let frame = ViewFrame::new(&view);
+            assert_same_input(&on_server.on_tick(view), &on_frame.decide(&frame));
+        }
+    }
+}
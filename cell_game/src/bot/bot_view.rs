@@ -0,0 +1,57 @@
+use crate::{
+    cells::{
+        cell::Cell, ejected_mass_cell::EjectedMassCell, food_cell::FoodCell,
+        player_cell::PlayerCell, virus_cell::VirusCell,
+    },
+    game_view::GameView,
+    ids::PlayerId,
+    pos::{Circle, Point},
+};
+
+/// A copy of everything a bot can see, split up the way strategies look at
+/// it. `GameView` can't be used as a trait object, so strategies are given
+/// this instead.
+pub struct BotView {
+    pub owner: PlayerId,
+    pub view_area: Circle,
+    pub own_cells: Vec<PlayerCell>,
+    /// Visible cells belonging to every other player
+    pub other_cells: Vec<PlayerCell>,
+    pub food: Vec<FoodCell>,
+    pub ejected_mass: Vec<EjectedMassCell>,
+    pub viruses: Vec<VirusCell>,
+}
+
+impl BotView {
+    pub fn new(view: &impl GameView) -> Self {
+        let owner = view.owner();
+        let (own_cells, other_cells) = view.player_cells().partition(|cell| cell.owner() == owner);
+        Self {
+            owner,
+            view_area: view.view_area(),
+            own_cells,
+            other_cells,
+            food: view.food_cells().collect(),
+            ejected_mass: view.ejected_mass_cells().collect(),
+            viruses: view.virus_cells().collect(),
+        }
+    }
+
+    /// The center of the bot's view, which is also the point its move vectors
+    /// are relative to
+    pub fn center(&self) -> Point {
+        self.view_area.center
+    }
+
+    pub fn largest_cell(&self) -> Option<&PlayerCell> {
+        self.own_cells
+            .iter()
+            .max_by(|a, b| a.mass().total_cmp(&b.mass()))
+    }
+
+    pub fn smallest_cell(&self) -> Option<&PlayerCell> {
+        self.own_cells
+            .iter()
+            .min_by(|a, b| a.mass().total_cmp(&b.mass()))
+    }
+}
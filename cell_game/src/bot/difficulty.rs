@@ -0,0 +1,44 @@
+use super::strategy::{FleeLarger, HuntSmaller, NearestFood, Strategy, Wander};
+
+/// How well a bot plays
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Difficulty {
+    /// Only goes after food, and reacts slowly
+    Easy,
+    /// Runs from larger players before going after food
+    Normal,
+    /// Runs from larger players, hunts smaller ones and splits to catch them
+    Hard,
+}
+
+impl Difficulty {
+    /// How many ticks pass between each decision a bot makes. Bots keep
+    /// repeating their last input in between.
+    pub fn reaction_ticks(self) -> u32 {
+        match self {
+            Self::Easy => 12,
+            Self::Normal => 5,
+            Self::Hard => 1,
+        }
+    }
+
+    /// The strategies a bot uses, from highest to lowest priority
+    pub fn strategies(self) -> Vec<Box<dyn Strategy>> {
+        match self {
+            Self::Easy => vec![Box::new(NearestFood), Box::new(Wander::default())],
+            Self::Normal => vec![
+                Box::new(FleeLarger { awareness: 3.0 }),
+                Box::new(NearestFood),
+                Box::new(Wander::default()),
+            ],
+            Self::Hard => vec![
+                Box::new(FleeLarger { awareness: 5.0 }),
+                Box::new(HuntSmaller {
+                    split_attacks: true,
+                }),
+                Box::new(NearestFood),
+                Box::new(Wander::default()),
+            ],
+        }
+    }
+}
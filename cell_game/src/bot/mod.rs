@@ -0,0 +1,4 @@
+pub mod bot_connection;
+pub mod bot_view;
+pub mod difficulty;
+pub mod strategy;
@@ -0,0 +1,142 @@
+use rand::{Rng, RngCore};
+
+use crate::{
+    cells::{cell::Cell, player_cell::PlayerCell},
+    client_connection::PlayerInput,
+    pos::Vec2,
+};
+
+use super::bot_view::BotView;
+
+/// One kind of behaviour for a bot. Bots ask each of their strategies in order
+/// of priority, and use the input from the first one that has something to
+/// do.
+pub trait Strategy {
+    /// Returns the input the bot should make, or `None` if this strategy
+    /// doesn't apply to what the bot can see
+    fn decide(&mut self, view: &BotView, rng: &mut dyn RngCore) -> Option<PlayerInput>;
+}
+
+/// Heads straight for the closest food or ejected mass
+pub struct NearestFood;
+
+impl Strategy for NearestFood {
+    fn decide(&mut self, view: &BotView, _rng: &mut dyn RngCore) -> Option<PlayerInput> {
+        let center = view.center();
+        view.food
+            .iter()
+            .map(|food| food.pos())
+            .chain(view.ejected_mass.iter().map(|ejected| ejected.pos()))
+            .map(|pos| center.vec_to(pos))
+            .min_by(|a, b| a.magnitude_squared().total_cmp(&b.magnitude_squared()))
+            .map(move_towards)
+    }
+}
+
+/// Runs away from nearby cells that could eat any of the bot's cells
+pub struct FleeLarger {
+    /// How many times the radius of the bot's cell a threat must be past its
+    /// own edge before the bot stops running from it
+    pub awareness: f64,
+}
+
+impl Strategy for FleeLarger {
+    fn decide(&mut self, view: &BotView, _rng: &mut dyn RngCore) -> Option<PlayerInput> {
+        let mut escape = Vec2::ZERO;
+        for cell in view.own_cells.iter() {
+            for threat in view.other_cells.iter().filter(|other| can_eat(other, cell)) {
+                let danger_dist = threat.radius() + cell.radius() * self.awareness;
+                let away = threat.pos().vec_to(cell.pos());
+                let dist = away.magnitude();
+                if dist >= danger_dist || dist == 0.0 {
+                    continue;
+                }
+                // Closer threats push harder
+                escape = escape + away / dist * (1.0 - dist / danger_dist);
+            }
+        }
+        (escape.magnitude_squared() > 0.0)
+            .then(|| move_towards(escape.normalize() * view.view_area.radius))
+    }
+}
+
+/// Chases the closest cell that the bot's largest cell can eat. Optionally
+/// splits to catch prey that is just out of reach.
+pub struct HuntSmaller {
+    pub split_attacks: bool,
+}
+
+impl HuntSmaller {
+    /// About how far a newly split cell travels before its launch slows down
+    const SPLIT_REACH: f64 = PlayerCell::SPLIT_SPEED / (1.0 - PlayerCell::VELOCITY_DECAY);
+    /// Bots stop splitting to attack once they are in this many pieces
+    const MAX_ATTACK_PIECES: usize = 4;
+}
+
+impl Strategy for HuntSmaller {
+    fn decide(&mut self, view: &BotView, _rng: &mut dyn RngCore) -> Option<PlayerInput> {
+        let hunter = view.largest_cell()?;
+        let prey = view
+            .other_cells
+            .iter()
+            .filter(|other| can_eat(hunter, other))
+            .min_by(|a, b| {
+                let a_dist = hunter.pos().squared_dist_to(a.pos());
+                let b_dist = hunter.pos().squared_dist_to(b.pos());
+                a_dist.total_cmp(&b_dist)
+            })?;
+
+        // A split launches half of the hunter, which must still be large
+        // enough to eat the prey when it lands
+        let half_can_eat = hunter.mass() / 2.0 >= prey.mass() * PlayerCell::EAT_MASS_RATIO;
+        let in_reach = hunter.pos().dist_to(prey.pos()) < Self::SPLIT_REACH + hunter.radius();
+        let mut input = move_towards(view.center().vec_to(prey.pos()));
+        input.split = self.split_attacks
+            && half_can_eat
+            && in_reach
+            && view.own_cells.len() < Self::MAX_ATTACK_PIECES;
+        Some(input)
+    }
+}
+
+/// Wanders in a random direction, turning every so often. This always has
+/// something to do, so it's used as a last resort.
+pub struct Wander {
+    /// The chance each tick of picking a new direction
+    pub turn_chance: f64,
+    direction: Vec2,
+}
+
+impl Default for Wander {
+    fn default() -> Self {
+        Self {
+            turn_chance: 0.02,
+            direction: Vec2::ZERO,
+        }
+    }
+}
+
+impl Strategy for Wander {
+    fn decide(&mut self, view: &BotView, rng: &mut dyn RngCore) -> Option<PlayerInput> {
+        if self.direction.magnitude_squared() == 0.0 || rng.gen_bool(self.turn_chance) {
+            let angle = rng.gen::<f64>() * std::f64::consts::TAU;
+            self.direction = Vec2 {
+                x: angle.cos(),
+                y: angle.sin(),
+            };
+        }
+        Some(move_towards(self.direction * view.view_area.radius))
+    }
+}
+
+/// Whether `eater` is massive enough to eat `prey`, ignoring where they are
+fn can_eat(eater: &PlayerCell, prey: &PlayerCell) -> bool {
+    eater.mass() >= prey.mass() * PlayerCell::EAT_MASS_RATIO
+}
+
+fn move_towards(move_vec: Vec2) -> PlayerInput {
+    PlayerInput {
+        move_vec,
+        ..Default::default()
+    }
+}
@@ -1,6 +1,6 @@
 use std::f64;
 
-use crate::pos::{Circle, Point};
+use crate::pos::{Circle, Point, Rect, Vec2};
 
 pub trait Cell: Clone + Sized {
     const MASS_AREA_MULTIPLIER: f64 = f64::consts::PI * 5.0;
@@ -24,3 +24,10 @@ pub trait Cell: Clone + Sized {
         }
     }
 }
+
+/// Moves a cell by its velocity without leaving `bounds`, then keeps `decay`
+/// of the velocity for the next tick
+pub(crate) fn apply_velocity(pos: &mut Point, velocity: &mut Vec2, decay: f64, bounds: Rect) {
+    *pos = bounds.clamp_pos(pos.offset(*velocity));
+    *velocity = *velocity * decay;
+}
@@ -0,0 +1,64 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    color::HSL,
+    pos::{Point, Rect, Vec2},
+};
+
+use super::cell::{self, Cell};
+
+/// A small chunk of mass shot out by a player. It can be eaten by any player
+/// cell, including the one that ejected it.
+#[derive(Clone, Deserialize, Serialize)]
+pub struct EjectedMassCell {
+    pos: Point,
+    velocity: Vec2,
+    color: HSL,
+}
+
+impl EjectedMassCell {
+    pub const MASS: f64 = 10.0;
+    /// The speed an ejected cell is launched at
+    pub const EJECT_SPEED: f64 = 30.0;
+    /// How much of its speed ejected mass keeps each tick
+    pub const VELOCITY_DECAY: f64 = 0.85;
+
+    pub(crate) fn new(pos: Point, velocity: Vec2, color: HSL) -> Self {
+        Self {
+            pos,
+            velocity,
+            color,
+        }
+    }
+
+    pub fn apply_velocity(&mut self, bounds: Rect) {
+        cell::apply_velocity(
+            &mut self.pos,
+            &mut self.velocity,
+            Self::VELOCITY_DECAY,
+            bounds,
+        );
+    }
+
+    pub fn velocity(&self) -> Vec2 {
+        self.velocity
+    }
+
+    pub fn color(&self) -> HSL {
+        self.color
+    }
+
+    pub fn pos_mut(&mut self) -> &mut Point {
+        &mut self.pos
+    }
+}
+
+impl Cell for EjectedMassCell {
+    fn pos(&self) -> Point {
+        self.pos
+    }
+
+    fn mass(&self) -> f64 {
+        Self::MASS
+    }
+}
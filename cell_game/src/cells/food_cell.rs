@@ -1,14 +1,17 @@
-use rand::prelude::*;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
 
 use crate::{
     color::HSL,
+    ids::{FoodCellId, IdGenerator},
     pos::{Point, Rect},
 };
 
 use super::cell::Cell;
 
-#[derive(Clone)]
+#[derive(Clone, Deserialize, Serialize)]
 pub struct FoodCell {
+    id: FoodCellId,
     pos: Point,
     color: HSL,
 }
@@ -16,23 +19,39 @@ pub struct FoodCell {
 impl FoodCell {
     pub const MASS: f64 = 1.0;
 
-    pub fn new(pos: Point) -> Self {
+    pub fn new(id: FoodCellId, pos: Point, rng: &mut impl Rng) -> Self {
         Self {
+            id,
             pos,
-            color: HSL::new(random(), 245, 105),
+            color: HSL::new(rng.gen(), 245, 105),
         }
     }
 
-    pub fn new_within(bounds: Rect) -> Self {
-        Self::new(Point {
-            x: bounds.min_x() + (bounds.width * random::<f64>()),
-            y: bounds.min_y() + (bounds.height * random::<f64>()),
-        })
+    /// Recreates a cell that was received from a server
+    pub(crate) fn from_parts(id: FoodCellId, pos: Point, color: HSL) -> Self {
+        Self { id, pos, color }
+    }
+
+    pub(crate) fn new_within(
+        bounds: Rect,
+        id_generator: &mut IdGenerator<FoodCellId>,
+        rng: &mut impl Rng,
+    ) -> Self {
+        let pos = bounds.random_point(rng);
+        Self::new(id_generator.next(), pos, rng)
+    }
+
+    pub fn id(&self) -> FoodCellId {
+        self.id
     }
 
     pub fn color(&self) -> HSL {
         self.color
     }
+
+    pub fn pos_mut(&mut self) -> &mut Point {
+        &mut self.pos
+    }
 }
 
 impl Cell for FoodCell {
@@ -1,3 +1,5 @@
 pub mod cell;
+pub mod ejected_mass_cell;
 pub mod food_cell;
 pub mod player_cell;
+pub mod virus_cell;
@@ -1,52 +1,264 @@
+use serde::{Deserialize, Serialize};
+
 use crate::{
+    color::HSL,
     ids::{IdGenerator, PlayerCellId, PlayerId},
     pos::{Point, Rect, Vec2},
-    server::game_server::GameServer,
+    server::game_config::{GameConfig, SpeedCurve},
 };
 
-use super::cell::Cell;
+use super::{
+    cell::{self, Cell},
+    ejected_mass_cell::EjectedMassCell,
+    virus_cell::VirusCell,
+};
 
-#[derive(Clone)]
+#[derive(Clone, Deserialize, Serialize)]
 pub struct PlayerCell {
     pos: Point,
     mass: f64,
     owner: PlayerId,
     id: PlayerCellId,
+    /// Extra motion applied on top of player movement, such as the launch of
+    /// a newly split cell. Decays every tick.
+    velocity: Vec2,
+    /// Ticks left until the cell can merge with other cells of the same owner
+    merge_cooldown: u32,
 }
 
 impl PlayerCell {
-    pub const MAX_MOVE_SPEED: f64 = 10.0;
-    pub const NEW_SPAWN_MASS: f64 = 20.0;
-    pub const MASS_PERCENT_LOSS_PER_TICK: f64 = 0.01 / GameServer::TICK_RATE as f64;
+    /// The speed a newly split cell is launched at
+    pub const SPLIT_SPEED: f64 = 25.0;
+    /// How much of a split cell's launch speed it keeps each tick
+    pub const VELOCITY_DECAY: f64 = 0.85;
+    /// The merge cooldown every cell has regardless of mass
+    pub const BASE_MERGE_COOLDOWN_SECS: f64 = 10.0;
+    /// How much longer the merge cooldown lasts for each unit of mass
+    pub const MERGE_COOLDOWN_SECS_PER_MASS: f64 = 0.02;
+    /// How many times more massive a cell must be than another player's cell
+    /// to eat it
+    pub const EAT_MASS_RATIO: f64 = 1.25;
+    /// The mass a cell loses when ejecting mass. This is more than the mass
+    /// of the ejected cell so that ejecting back and forth loses mass.
+    pub const EJECT_MASS_COST: f64 = 12.0;
 
     pub(crate) fn new(
         pos: Point,
         owner: PlayerId,
+        config: &GameConfig,
         id_generator: &mut IdGenerator<PlayerCellId>,
     ) -> Self {
         Self {
             pos,
-            mass: Self::NEW_SPAWN_MASS,
+            mass: config.spawn_mass,
             owner,
             id: id_generator.next(),
+            velocity: Vec2::ZERO,
+            merge_cooldown: Self::merge_cooldown_for(config.spawn_mass, config),
         }
     }
 
-    fn clamped_vec_to(&mut self, pos: Point) -> Vec2 {
-        self.pos.vec_to(pos).max_magnitude(Self::MAX_MOVE_SPEED)
+    /// Recreates a cell that was received from a server. The merge cooldown
+    /// isn't sent to clients, so the cell is treated as able to merge.
+    pub(crate) fn from_parts(
+        pos: Point,
+        mass: f64,
+        owner: PlayerId,
+        id: PlayerCellId,
+        velocity: Vec2,
+    ) -> Self {
+        Self {
+            pos,
+            mass,
+            owner,
+            id,
+            velocity,
+            merge_cooldown: 0,
+        }
+    }
+
+    fn merge_cooldown_for(mass: f64, config: &GameConfig) -> u32 {
+        let secs = Self::BASE_MERGE_COOLDOWN_SECS + mass * Self::MERGE_COOLDOWN_SECS_PER_MASS;
+        (secs * config.tick_rate as f64) as u32
+    }
+
+    fn clamped_vec_to(&mut self, pos: Point, speed_curve: &SpeedCurve) -> Vec2 {
+        self.pos
+            .vec_to(pos)
+            .max_magnitude(speed_curve.max_speed(self.mass))
     }
 
-    pub fn move_player(&mut self, move_to: Point, bounds: Rect) {
-        let move_vec = self.clamped_vec_to(bounds.clamp_pos(move_to));
+    /// Moves the cell towards `move_to` as fast as its mass allows. This does
+    /// not include the cell's velocity, which is applied separately so that
+    /// launched cells can move faster than their max speed.
+    pub fn move_player(&mut self, move_to: Point, bounds: Rect, speed_curve: &SpeedCurve) {
+        let move_vec = self.clamped_vec_to(bounds.clamp_pos(move_to), speed_curve);
         self.pos = self.pos.offset(move_vec);
     }
 
+    pub fn apply_velocity(&mut self, bounds: Rect) {
+        cell::apply_velocity(
+            &mut self.pos,
+            &mut self.velocity,
+            Self::VELOCITY_DECAY,
+            bounds,
+        );
+    }
+
+    /// Cells must be at least twice the spawn mass to split, so that neither
+    /// half is smaller than a new cell.
+    pub fn can_split(&self, config: &GameConfig) -> bool {
+        self.mass >= config.spawn_mass * 2.0
+    }
+
+    /// Halves the mass of the cell, returning a new cell with the other half
+    /// that is launched towards `towards`. Returns `None` if the cell is too
+    /// small to split.
+    pub(crate) fn split(
+        &mut self,
+        towards: Point,
+        config: &GameConfig,
+        id_generator: &mut IdGenerator<PlayerCellId>,
+    ) -> Option<Self> {
+        if !self.can_split(config) {
+            return None;
+        }
+        self.mass /= 2.0;
+        self.merge_cooldown = Self::merge_cooldown_for(self.mass, config);
+        let direction = self.direction_to(towards).unwrap_or(Vec2::ZERO);
+        Some(self.launch_piece(self.mass, direction, config, id_generator))
+    }
+
+    /// Whether this cell is large enough and close enough to eat a virus, which
+    /// pops it
+    pub fn can_pop_on(&self, virus: &VirusCell) -> bool {
+        self.mass >= virus.mass() * Self::EAT_MASS_RATIO
+            && self.hitbox().contains_point(virus.pos())
+    }
+
+    /// Breaks the cell into at most `max_pieces` additional pieces of equal
+    /// mass that are launched out in every direction. Pieces are never smaller
+    /// than the spawn mass.
+    pub(crate) fn pop(
+        &mut self,
+        max_pieces: usize,
+        config: &GameConfig,
+        id_generator: &mut IdGenerator<PlayerCellId>,
+    ) -> Vec<Self> {
+        let pieces = max_pieces.min(((self.mass / config.spawn_mass) as usize).saturating_sub(1));
+        if pieces == 0 {
+            return Vec::new();
+        }
+        self.mass /= (pieces + 1) as f64;
+        self.merge_cooldown = Self::merge_cooldown_for(self.mass, config);
+        (0..pieces)
+            .map(|i| {
+                let angle = std::f64::consts::TAU * i as f64 / pieces as f64;
+                let direction = Vec2 {
+                    x: angle.cos(),
+                    y: angle.sin(),
+                };
+                self.launch_piece(self.mass, direction, config, id_generator)
+            })
+            .collect()
+    }
+
+    /// Creates a new cell for the same owner at this cell's position, launched
+    /// in the direction of the unit vector `direction`
+    fn launch_piece(
+        &self,
+        mass: f64,
+        direction: Vec2,
+        config: &GameConfig,
+        id_generator: &mut IdGenerator<PlayerCellId>,
+    ) -> Self {
+        Self {
+            pos: self.pos,
+            mass,
+            owner: self.owner,
+            id: id_generator.next(),
+            velocity: direction * Self::SPLIT_SPEED,
+            merge_cooldown: Self::merge_cooldown_for(mass, config),
+        }
+    }
+
+    /// Shoots a small cell of mass out of the edge of this cell towards
+    /// `towards`. Returns `None` if ejecting would leave the cell smaller than
+    /// the spawn mass, or if there is no direction to eject in.
+    pub(crate) fn eject(
+        &mut self,
+        towards: Point,
+        color: HSL,
+        config: &GameConfig,
+    ) -> Option<EjectedMassCell> {
+        if self.mass < config.spawn_mass + Self::EJECT_MASS_COST {
+            return None;
+        }
+        let direction = self.direction_to(towards)?;
+        self.mass -= Self::EJECT_MASS_COST;
+        let pos = self.pos.offset(direction * self.radius());
+        Some(EjectedMassCell::new(
+            pos,
+            direction * EjectedMassCell::EJECT_SPEED,
+            color,
+        ))
+    }
+
+    /// The unit vector pointing from this cell towards a position
+    fn direction_to(&self, pos: Point) -> Option<Vec2> {
+        let vec = self.pos.vec_to(pos);
+        (vec.magnitude_squared() > 0.0).then(|| vec.normalize())
+    }
+
+    pub fn tick_merge_cooldown(&mut self) {
+        self.merge_cooldown = self.merge_cooldown.saturating_sub(1);
+    }
+
+    pub fn can_merge(&self) -> bool {
+        self.merge_cooldown == 0
+    }
+
+    /// Pushes two overlapping cells apart so that they are just touching.
+    /// Lighter cells are pushed further than heavier ones.
+    pub(crate) fn push_apart(&mut self, other: &mut Self, bounds: Rect) {
+        let dist = self.pos.dist_to(other.pos);
+        let overlap = self.radius() + other.radius() - dist;
+        if overlap <= 0.0 {
+            return;
+        }
+        // Cells on the exact same spot have no direction between them, so
+        // pick an arbitrary one.
+        let direction = if dist > 0.0 {
+            other.pos.vec_to(self.pos) / dist
+        } else {
+            Vec2 { x: 1.0, y: 0.0 }
+        };
+        let total_mass = self.mass + other.mass;
+        self.pos = bounds.clamp_pos(
+            self.pos
+                .offset(direction * (overlap * other.mass / total_mass)),
+        );
+        other.pos = bounds.clamp_pos(
+            other
+                .pos
+                .offset(direction * (-overlap * self.mass / total_mass)),
+        );
+    }
+
+    /// Whether this cell is large enough and close enough to eat another
+    /// player's cell
+    pub fn can_eat(&self, other: &Self) -> bool {
+        self.owner != other.owner
+            && self.mass >= other.mass * Self::EAT_MASS_RATIO
+            && self.hitbox().contains_point(other.pos)
+    }
+
     pub fn add_mass(&mut self, mass: f64) {
         self.mass += mass
     }
 
-    pub fn lose_mass(&mut self) {
-        self.mass = (self.mass * (1.0 - Self::MASS_PERCENT_LOSS_PER_TICK)).max(Self::NEW_SPAWN_MASS)
+    pub fn lose_mass(&mut self, config: &GameConfig) {
+        self.mass = (self.mass * (1.0 - config.mass_loss_per_tick())).max(config.spawn_mass)
     }
 
     pub fn owner(&self) -> PlayerId {
@@ -57,6 +269,10 @@ impl PlayerCell {
         self.id
     }
 
+    pub fn velocity(&self) -> Vec2 {
+        self.velocity
+    }
+
     pub fn pos_mut(&mut self) -> &mut Point {
         &mut self.pos
     }
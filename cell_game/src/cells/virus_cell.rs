@@ -0,0 +1,109 @@
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    color::RGBA,
+    pos::{Point, Rect, Vec2},
+};
+
+use super::cell::{self, Cell};
+
+/// A spiky cell that pops player cells large enough to eat it into many
+/// pieces. Smaller cells can hide underneath it.
+#[derive(Clone, Deserialize, Serialize)]
+pub struct VirusCell {
+    pos: Point,
+    mass: f64,
+    velocity: Vec2,
+    /// How many ejected mass cells the virus has eaten since it last split
+    times_fed: u32,
+}
+
+impl VirusCell {
+    pub const BASE_MASS: f64 = 100.0;
+    pub const COLOR: RGBA = RGBA::new(51, 255, 51, 255);
+    /// How many ejected mass cells a virus must eat before it shoots out a
+    /// new virus
+    pub const FEEDS_TO_SPLIT: u32 = 7;
+    /// The speed a virus shot out of another virus is launched at
+    pub const SHOOT_SPEED: f64 = 40.0;
+    /// How much of its speed a shot out virus keeps each tick
+    pub const VELOCITY_DECAY: f64 = 0.9;
+
+    pub fn new(pos: Point) -> Self {
+        Self {
+            pos,
+            mass: Self::BASE_MASS,
+            velocity: Vec2::ZERO,
+            times_fed: 0,
+        }
+    }
+
+    /// Recreates a virus that was received from a server. The virus is
+    /// treated as not having been fed.
+    pub(crate) fn from_parts(pos: Point, mass: f64, velocity: Vec2) -> Self {
+        Self {
+            pos,
+            mass,
+            velocity,
+            times_fed: 0,
+        }
+    }
+
+    pub fn new_within(bounds: Rect, rng: &mut impl Rng) -> Self {
+        Self::new(bounds.random_point(rng))
+    }
+
+    pub fn apply_velocity(&mut self, bounds: Rect) {
+        cell::apply_velocity(
+            &mut self.pos,
+            &mut self.velocity,
+            Self::VELOCITY_DECAY,
+            bounds,
+        );
+    }
+
+    /// Absorbs ejected mass that was moving with `velocity`. Once the virus
+    /// has been fed enough it shrinks back to its base mass and returns a new
+    /// virus shot out in the direction that it was fed from.
+    pub(crate) fn feed(&mut self, mass: f64, velocity: Vec2) -> Option<Self> {
+        self.mass += mass;
+        self.times_fed += 1;
+        if self.times_fed < Self::FEEDS_TO_SPLIT {
+            return None;
+        }
+        self.mass = Self::BASE_MASS;
+        self.times_fed = 0;
+        let direction = if velocity.magnitude_squared() > 0.0 {
+            velocity.normalize()
+        } else {
+            Vec2 { x: 1.0, y: 0.0 }
+        };
+        Some(Self {
+            velocity: direction * Self::SHOOT_SPEED,
+            ..Self::new(self.pos)
+        })
+    }
+
+    pub fn velocity(&self) -> Vec2 {
+        self.velocity
+    }
+
+    pub fn color(&self) -> RGBA {
+        Self::COLOR
+    }
+
+    pub fn pos_mut(&mut self) -> &mut Point {
+        &mut self.pos
+    }
+}
+
+impl Cell for VirusCell {
+    fn pos(&self) -> Point {
+        self.pos
+    }
+
+    fn mass(&self) -> f64 {
+        self.mass
+    }
+}
@@ -1,16 +1,35 @@
-use crate::{game_view::GameView, pos::Vec2};
+use serde::{Deserialize, Serialize};
 
-#[derive(Clone)]
+use crate::{game_view::GameView, ids::PlayerId, pos::Vec2};
+
+/// The default input moves towards the center of the view area without
+/// splitting, ejecting or freezing
+#[derive(Clone, Default, Deserialize, Serialize)]
 pub struct PlayerInput {
     /// A vector relative to the the view area of the player. The vector is
     /// centered on the center of the view area, and the magnitude is scaled
     /// by the view radius. This is done to keep the direction of player motion
     /// relatively consistent when working off of cached inputs.
     pub move_vec: Vec2,
+    /// Splits each of the player's cells in half, launching the new halves
+    /// in the direction of movement.
+    pub split: bool,
+    /// Shoots a small amount of mass out of each of the player's cells in the
+    /// direction of movement.
+    pub eject: bool,
+    /// Keeps the player's cells where they are instead of moving them
+    /// towards `move_vec`. Splitting and ejecting still aim along `move_vec`.
+    #[serde(default)]
+    pub freeze: bool,
 }
 
 pub trait ClientConnection<'a> {
     type V: GameView;
 
     fn on_tick(&mut self, view: Self::V) -> PlayerInput;
+
+    /// Called once when the player loses their last cell. The connection
+    /// keeps receiving views of the area the player died in, but its inputs
+    /// are ignored.
+    fn on_death(&mut self, _killer: Option<PlayerId>) {}
 }
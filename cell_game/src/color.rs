@@ -1,6 +1,9 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
 pub trait Color: ToString + Copy {}
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Deserialize, Serialize)]
 pub struct RGBA {
     red: u8,
     green: u8,
@@ -9,7 +12,7 @@ pub struct RGBA {
 }
 
 impl RGBA {
-    pub fn new(red: u8, green: u8, blue: u8, alpha: u8) -> Self {
+    pub const fn new(red: u8, green: u8, blue: u8, alpha: u8) -> Self {
         Self {
             red,
             green,
@@ -19,9 +22,10 @@ impl RGBA {
     }
 }
 
-impl ToString for RGBA {
-    fn to_string(&self) -> String {
-        format!(
+impl fmt::Display for RGBA {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
             "#{:02x}{:02x}{:02x}{:02x}",
             self.red, self.green, self.blue, self.alpha
         )
@@ -30,7 +34,7 @@ impl ToString for RGBA {
 
 impl Color for RGBA {}
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Deserialize, Serialize)]
 pub struct HSL {
     hue: u8,
     saturation: u8,
@@ -45,11 +49,24 @@ impl HSL {
             lightness,
         }
     }
+
+    pub fn hue(self) -> u8 {
+        self.hue
+    }
+
+    pub fn saturation(self) -> u8 {
+        self.saturation
+    }
+
+    pub fn lightness(self) -> u8 {
+        self.lightness
+    }
 }
 
-impl ToString for HSL {
-    fn to_string(&self) -> String {
-        format!(
+impl fmt::Display for HSL {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
             "hsl({},{}%,{}%)",
             self.hue,
             (self.saturation as f64 / u8::MAX as f64) * 100.0,
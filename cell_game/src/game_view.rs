@@ -1,15 +1,26 @@
 use crate::{
-    cells::{food_cell::FoodCell, player_cell::PlayerCell},
+    cells::{
+        ejected_mass_cell::EjectedMassCell, food_cell::FoodCell, player_cell::PlayerCell,
+        virus_cell::VirusCell,
+    },
     ids::PlayerId,
+    leaderboard::LeaderboardEntry,
     player_info::PlayerInfo,
-    pos::Circle,
+    pos::{Circle, Rect},
 };
 
 pub trait GameView {
     fn player_cells(&self) -> impl Iterator<Item = PlayerCell>;
     fn food_cells(&self) -> impl Iterator<Item = FoodCell>;
+    fn ejected_mass_cells(&self) -> impl Iterator<Item = EjectedMassCell>;
+    fn virus_cells(&self) -> impl Iterator<Item = VirusCell>;
     fn player_infos(&self) -> impl Iterator<Item = &PlayerInfo>;
     fn view_area(&self) -> Circle;
+    /// The area that the whole game takes place in
+    fn world_bounds(&self) -> Rect;
     /// The player who the view belongs to
     fn owner(&self) -> PlayerId;
+    /// The most massive players in order of rank, followed by the owner's own
+    /// entry if they're alive but not among them
+    fn leaderboard(&self) -> impl Iterator<Item = LeaderboardEntry>;
 }
@@ -1,25 +1,49 @@
+use serde::{Deserialize, Serialize};
 use std::marker::PhantomData;
 
 pub trait Id {
     fn new(id: u32) -> Self;
+
+    fn value(self) -> u32;
 }
 
-#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Deserialize, Serialize)]
 pub struct PlayerId(u32);
 
 impl Id for PlayerId {
     fn new(id: u32) -> Self {
         Self(id)
     }
+
+    fn value(self) -> u32 {
+        self.0
+    }
 }
 
-#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Deserialize, Serialize)]
 pub struct PlayerCellId(u32);
 
 impl Id for PlayerCellId {
     fn new(id: u32) -> Self {
         Self(id)
     }
+
+    fn value(self) -> u32 {
+        self.0
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Deserialize, Serialize)]
+pub struct FoodCellId(u32);
+
+impl Id for FoodCellId {
+    fn new(id: u32) -> Self {
+        Self(id)
+    }
+
+    fn value(self) -> u32 {
+        self.0
+    }
 }
 
 pub(crate) struct IdGenerator<T: Id>(u32, PhantomData<T>);
@@ -0,0 +1,69 @@
+use std::{cmp::Ordering, collections::HashMap};
+
+use crate::{
+    cells::{cell::Cell, player_cell::PlayerCell},
+    ids::PlayerId,
+};
+
+/// A living player's place in the ranking of players by total mass
+#[derive(Clone, Copy, PartialEq)]
+pub struct LeaderboardEntry {
+    /// 1 for the most massive player
+    pub rank: u32,
+    pub player: PlayerId,
+    /// The total mass of all of the player's cells
+    pub mass: f64,
+}
+
+/// Every living player ranked by total mass, as of the end of the last tick
+pub struct Leaderboard {
+    ranking: Vec<LeaderboardEntry>,
+    /// How many of the top players everyone is shown
+    size: usize,
+}
+
+impl Leaderboard {
+    pub(crate) fn new(size: usize) -> Self {
+        Self {
+            ranking: Vec::new(),
+            size,
+        }
+    }
+
+    /// Ranks every player that has cells by their total mass, largest first.
+    /// Players with the same mass are ranked by id, so the order is always
+    /// the same for the same cells.
+    pub(crate) fn update(&mut self, players: &[PlayerCell]) {
+        let mut masses = HashMap::new();
+        for cell in players {
+            *masses.entry(cell.owner()).or_insert(0.0) += cell.mass();
+        }
+        let mut totals: Vec<(PlayerId, f64)> = masses.into_iter().collect();
+        totals.sort_by(
+            |(a_id, a_mass), (b_id, b_mass)| match b_mass.total_cmp(a_mass) {
+                Ordering::Equal => a_id.cmp(b_id),
+                ordering => ordering,
+            },
+        );
+        self.ranking = totals
+            .into_iter()
+            .zip(1..)
+            .map(|((player, mass), rank)| LeaderboardEntry { rank, player, mass })
+            .collect();
+    }
+
+    /// The entries a player is shown: the top players, followed by the
+    /// player's own entry if they aren't among them
+    pub fn entries_for(&self, owner: PlayerId) -> impl Iterator<Item = LeaderboardEntry> + '_ {
+        let own_entry = self
+            .ranking
+            .iter()
+            .skip(self.size)
+            .find(|entry| entry.player == owner);
+        self.ranking
+            .iter()
+            .take(self.size)
+            .chain(own_entry)
+            .copied()
+    }
+}
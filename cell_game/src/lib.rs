@@ -1,8 +1,14 @@
+pub mod bot;
 pub mod cells;
 pub mod client_connection;
 pub mod color;
 pub mod game_view;
 pub mod ids;
+pub mod leaderboard;
 pub mod player_info;
 pub mod pos;
+pub mod protocol;
 pub mod server;
+pub mod spatial_index;
+#[cfg(any(test, feature = "test-support"))]
+pub mod test_support;
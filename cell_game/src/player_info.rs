@@ -1,11 +1,12 @@
-use rand::prelude::*;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
 
 use crate::{
     color::HSL,
     ids::{IdGenerator, PlayerId},
 };
 
-#[derive(Clone)]
+#[derive(Clone, Deserialize, Serialize)]
 pub struct PlayerInfo {
     id: PlayerId,
     name: String,
@@ -13,14 +14,23 @@ pub struct PlayerInfo {
 }
 
 impl PlayerInfo {
-    pub(crate) fn new(name: String, id_gen: &mut IdGenerator<PlayerId>) -> Self {
+    pub(crate) fn new(
+        name: String,
+        id_gen: &mut IdGenerator<PlayerId>,
+        rng: &mut impl Rng,
+    ) -> Self {
         Self {
             id: id_gen.next(),
             name,
-            color: HSL::new(random(), 255, 130),
+            color: HSL::new(rng.gen(), 255, 130),
         }
     }
 
+    /// Recreates info that was received from a server
+    pub(crate) fn from_parts(id: PlayerId, name: String, color: HSL) -> Self {
+        Self { id, name, color }
+    }
+
     pub fn id(&self) -> PlayerId {
         self.id
     }
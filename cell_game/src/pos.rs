@@ -1,6 +1,8 @@
-use std::ops::{Div, Mul};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::ops::{Add, Div, Mul};
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, PartialEq, Deserialize, Serialize)]
 pub struct Point {
     pub x: f64,
     pub y: f64,
@@ -32,7 +34,7 @@ impl Point {
     }
 }
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, PartialEq, Default, Deserialize, Serialize)]
 pub struct Vec2 {
     pub x: f64,
     pub y: f64,
@@ -66,6 +68,17 @@ impl Vec2 {
     }
 }
 
+impl Add for Vec2 {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self::Output {
+        Self::Output {
+            x: self.x + other.x,
+            y: self.y + other.y,
+        }
+    }
+}
+
 impl Mul<f64> for Vec2 {
     type Output = Self;
 
@@ -88,7 +101,7 @@ impl Div<f64> for Vec2 {
     }
 }
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, PartialEq, Deserialize, Serialize)]
 pub struct Rect {
     pub top_left: Point,
     /// Must be positive
@@ -106,6 +119,14 @@ impl Rect {
         }
     }
 
+    /// A uniformly random point within the rect
+    pub fn random_point(self, rng: &mut (impl Rng + ?Sized)) -> Point {
+        Point {
+            x: self.min_x() + self.width * rng.gen::<f64>(),
+            y: self.min_y() + self.height * rng.gen::<f64>(),
+        }
+    }
+
     pub fn contains_point(self, p: Point) -> bool {
         self.top_left.x <= p.x
             && p.x <= self.top_left.x + self.width
@@ -164,7 +185,7 @@ impl Rect {
     }
 }
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, PartialEq, Deserialize, Serialize)]
 pub struct Circle {
     pub center: Point,
     /// Radius must be positive
@@ -188,6 +209,18 @@ impl Circle {
         }
     }
 
+    /// The smallest rectangle that contains the whole circle
+    pub fn bounding_rect(self) -> Rect {
+        Rect {
+            top_left: self.center.offset(Vec2 {
+                x: -self.radius,
+                y: -self.radius,
+            }),
+            width: self.radius * 2.0,
+            height: self.radius * 2.0,
+        }
+    }
+
     /// aspect_ratio describes how many times wider the rectangle should be
     /// compared to its height
     pub fn fit_rect_within_circle(self, aspect_ratio: f64) -> Rect {
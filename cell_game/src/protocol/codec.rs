@@ -0,0 +1,733 @@
+use crate::{
+    cells::{
+        cell::Cell, ejected_mass_cell::EjectedMassCell, food_cell::FoodCell,
+        player_cell::PlayerCell, virus_cell::VirusCell,
+    },
+    client_connection::PlayerInput,
+    color::HSL,
+    ids::{FoodCellId, Id, PlayerCellId, PlayerId},
+    leaderboard::LeaderboardEntry,
+    player_info::PlayerInfo,
+    pos::{Circle, Point, Rect, Vec2},
+};
+
+use super::decode_error::DecodeError;
+
+/// Positions and lengths are sent as multiples of `1 / POSITION_SCALE`
+pub const POSITION_SCALE: f64 = 16.0;
+/// Masses are sent as multiples of `1 / MASS_SCALE`
+pub const MASS_SCALE: f64 = 100.0;
+/// Velocities are sent as multiples of `1 / VELOCITY_SCALE`
+pub const VELOCITY_SCALE: f64 = 64.0;
+/// Move vectors are relative to the view radius, so they need much finer
+/// steps than positions. They are sent as multiples of `1 / INPUT_SCALE`.
+pub const INPUT_SCALE: f64 = 4096.0;
+
+/// The longest a varint holding a `u64` can be
+const MAX_VARINT_LEN: usize = 10;
+
+pub trait Encode {
+    fn encode(&self, writer: &mut Writer);
+}
+
+pub trait Decode: Sized {
+    fn decode(reader: &mut Reader) -> Result<Self, DecodeError>;
+}
+
+/// Builds up the bytes of a frame
+#[derive(Default)]
+pub struct Writer {
+    bytes: Vec<u8>,
+}
+
+impl Writer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.bytes
+    }
+
+    pub fn u8(&mut self, value: u8) {
+        self.bytes.push(value)
+    }
+
+    /// Writes an unsigned LEB128 varint, which uses one byte for every seven
+    /// bits of the value
+    pub fn varint(&mut self, mut value: u64) {
+        while value >= 0x80 {
+            self.bytes.push(value as u8 | 0x80);
+            value >>= 7;
+        }
+        self.bytes.push(value as u8)
+    }
+
+    /// Writes a zigzag encoded varint, so that small negative values are as
+    /// short as small positive ones
+    pub fn signed_varint(&mut self, value: i64) {
+        self.varint(((value << 1) ^ (value >> 63)) as u64)
+    }
+
+    /// Writes a value rounded to the nearest multiple of `1 / scale`
+    pub fn quantized(&mut self, value: f64, scale: f64) {
+        self.signed_varint((value * scale).round() as i64)
+    }
+
+    /// Writes a value that can never be negative rounded to the nearest
+    /// multiple of `1 / scale`. Negative values are sent as zero.
+    pub fn quantized_unsigned(&mut self, value: f64, scale: f64) {
+        self.varint((value * scale).round() as u64)
+    }
+
+    pub fn string(&mut self, value: &str) {
+        self.varint(value.len() as u64);
+        self.bytes.extend_from_slice(value.as_bytes())
+    }
+
+    pub fn value(&mut self, value: &impl Encode) {
+        value.encode(self)
+    }
+
+    /// Writes the length of a list followed by each of its items
+    pub fn list<'a, T: Encode + 'a>(&mut self, items: impl ExactSizeIterator<Item = &'a T>) {
+        self.varint(items.len() as u64);
+        for item in items {
+            item.encode(self)
+        }
+    }
+}
+
+/// Reads values out of a frame in the order they were written
+pub struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    pub fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes }
+    }
+
+    /// How many bytes haven't been read yet
+    pub fn remaining(&self) -> usize {
+        self.bytes.len()
+    }
+
+    /// Fails if there are any bytes left that haven't been read
+    pub fn finish(&self) -> Result<(), DecodeError> {
+        match self.remaining() {
+            0 => Ok(()),
+            len => Err(DecodeError::TrailingBytes(len)),
+        }
+    }
+
+    pub fn u8(&mut self) -> Result<u8, DecodeError> {
+        let (&first, rest) = self.bytes.split_first().ok_or(DecodeError::UnexpectedEnd)?;
+        self.bytes = rest;
+        Ok(first)
+    }
+
+    pub fn varint(&mut self) -> Result<u64, DecodeError> {
+        let mut value = 0;
+        for i in 0..MAX_VARINT_LEN {
+            let byte = self.u8()?;
+            let bits = (byte & 0x7f) as u64;
+            // The last byte only has room for the single highest bit
+            if i == MAX_VARINT_LEN - 1 && bits > 1 {
+                return Err(DecodeError::VarintOverflow);
+            }
+            value |= bits << (7 * i);
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err(DecodeError::VarintOverflow)
+    }
+
+    pub fn signed_varint(&mut self) -> Result<i64, DecodeError> {
+        let value = self.varint()?;
+        Ok((value >> 1) as i64 ^ -((value & 1) as i64))
+    }
+
+    pub fn u16(&mut self, field: &'static str) -> Result<u16, DecodeError> {
+        self.varint()?
+            .try_into()
+            .map_err(|_| DecodeError::OutOfRange(field))
+    }
+
+    pub fn u32(&mut self, field: &'static str) -> Result<u32, DecodeError> {
+        self.varint()?
+            .try_into()
+            .map_err(|_| DecodeError::OutOfRange(field))
+    }
+
+    pub fn quantized(&mut self, scale: f64) -> Result<f64, DecodeError> {
+        Ok(self.signed_varint()? as f64 / scale)
+    }
+
+    pub fn quantized_unsigned(&mut self, scale: f64) -> Result<f64, DecodeError> {
+        Ok(self.varint()? as f64 / scale)
+    }
+
+    pub fn string(&mut self) -> Result<String, DecodeError> {
+        let len = self.length()?;
+        let (bytes, rest) = self.bytes.split_at(len);
+        self.bytes = rest;
+        String::from_utf8(bytes.to_vec()).map_err(|_| DecodeError::InvalidUtf8)
+    }
+
+    pub fn value<T: Decode>(&mut self) -> Result<T, DecodeError> {
+        T::decode(self)
+    }
+
+    pub fn list<T: Decode>(&mut self) -> Result<Vec<T>, DecodeError> {
+        let len = self.length()?;
+        (0..len).map(|_| T::decode(self)).collect()
+    }
+
+    /// Reads the length of a string or list. Every item takes up at least one
+    /// byte, so a length longer than the rest of the frame is rejected before
+    /// anything is allocated for it.
+    fn length(&mut self) -> Result<usize, DecodeError> {
+        let len = self.varint()?;
+        if len > self.remaining() as u64 {
+            return Err(DecodeError::UnexpectedEnd);
+        }
+        Ok(len as usize)
+    }
+}
+
+impl Encode for Point {
+    fn encode(&self, writer: &mut Writer) {
+        writer.quantized(self.x, POSITION_SCALE);
+        writer.quantized(self.y, POSITION_SCALE);
+    }
+}
+
+impl Decode for Point {
+    fn decode(reader: &mut Reader) -> Result<Self, DecodeError> {
+        Ok(Self {
+            x: reader.quantized(POSITION_SCALE)?,
+            y: reader.quantized(POSITION_SCALE)?,
+        })
+    }
+}
+
+/// Vectors are encoded as velocities. Move vectors use their own scale, so
+/// they're encoded as part of [`PlayerInput`].
+impl Encode for Vec2 {
+    fn encode(&self, writer: &mut Writer) {
+        writer.quantized(self.x, VELOCITY_SCALE);
+        writer.quantized(self.y, VELOCITY_SCALE);
+    }
+}
+
+impl Decode for Vec2 {
+    fn decode(reader: &mut Reader) -> Result<Self, DecodeError> {
+        Ok(Self {
+            x: reader.quantized(VELOCITY_SCALE)?,
+            y: reader.quantized(VELOCITY_SCALE)?,
+        })
+    }
+}
+
+impl Encode for Circle {
+    fn encode(&self, writer: &mut Writer) {
+        writer.value(&self.center);
+        writer.quantized_unsigned(self.radius, POSITION_SCALE);
+    }
+}
+
+impl Decode for Circle {
+    fn decode(reader: &mut Reader) -> Result<Self, DecodeError> {
+        Ok(Self {
+            center: reader.value()?,
+            radius: reader.quantized_unsigned(POSITION_SCALE)?,
+        })
+    }
+}
+
+impl Encode for Rect {
+    fn encode(&self, writer: &mut Writer) {
+        writer.value(&self.top_left);
+        writer.quantized_unsigned(self.width, POSITION_SCALE);
+        writer.quantized_unsigned(self.height, POSITION_SCALE);
+    }
+}
+
+impl Decode for Rect {
+    fn decode(reader: &mut Reader) -> Result<Self, DecodeError> {
+        Ok(Self {
+            top_left: reader.value()?,
+            width: reader.quantized_unsigned(POSITION_SCALE)?,
+            height: reader.quantized_unsigned(POSITION_SCALE)?,
+        })
+    }
+}
+
+impl Encode for HSL {
+    fn encode(&self, writer: &mut Writer) {
+        writer.u8(self.hue());
+        writer.u8(self.saturation());
+        writer.u8(self.lightness());
+    }
+}
+
+impl Decode for HSL {
+    fn decode(reader: &mut Reader) -> Result<Self, DecodeError> {
+        Ok(Self::new(reader.u8()?, reader.u8()?, reader.u8()?))
+    }
+}
+
+impl Encode for PlayerId {
+    fn encode(&self, writer: &mut Writer) {
+        writer.varint(self.value() as u64)
+    }
+}
+
+impl Decode for PlayerId {
+    fn decode(reader: &mut Reader) -> Result<Self, DecodeError> {
+        Ok(Self::new(reader.u32("player id")?))
+    }
+}
+
+impl Encode for PlayerCellId {
+    fn encode(&self, writer: &mut Writer) {
+        writer.varint(self.value() as u64)
+    }
+}
+
+impl Decode for PlayerCellId {
+    fn decode(reader: &mut Reader) -> Result<Self, DecodeError> {
+        Ok(Self::new(reader.u32("player cell id")?))
+    }
+}
+
+impl Encode for FoodCellId {
+    fn encode(&self, writer: &mut Writer) {
+        writer.varint(self.value() as u64)
+    }
+}
+
+impl Decode for FoodCellId {
+    fn decode(reader: &mut Reader) -> Result<Self, DecodeError> {
+        Ok(Self::new(reader.u32("food cell id")?))
+    }
+}
+
+impl Encode for PlayerInfo {
+    fn encode(&self, writer: &mut Writer) {
+        writer.value(&self.id());
+        writer.string(self.name());
+        writer.value(&self.color());
+    }
+}
+
+impl Decode for PlayerInfo {
+    fn decode(reader: &mut Reader) -> Result<Self, DecodeError> {
+        Ok(Self::from_parts(
+            reader.value()?,
+            reader.string()?,
+            reader.value()?,
+        ))
+    }
+}
+
+impl Encode for LeaderboardEntry {
+    fn encode(&self, writer: &mut Writer) {
+        writer.varint(self.rank as u64);
+        writer.value(&self.player);
+        writer.quantized_unsigned(self.mass, MASS_SCALE);
+    }
+}
+
+impl Decode for LeaderboardEntry {
+    fn decode(reader: &mut Reader) -> Result<Self, DecodeError> {
+        Ok(Self {
+            rank: reader.u32("leaderboard rank")?,
+            player: reader.value()?,
+            mass: reader.quantized_unsigned(MASS_SCALE)?,
+        })
+    }
+}
+
+impl Encode for PlayerCell {
+    fn encode(&self, writer: &mut Writer) {
+        writer.value(&self.id());
+        writer.value(&self.owner());
+        writer.value(&self.pos());
+        writer.quantized_unsigned(self.mass(), MASS_SCALE);
+        writer.value(&self.velocity());
+    }
+}
+
+impl Decode for PlayerCell {
+    fn decode(reader: &mut Reader) -> Result<Self, DecodeError> {
+        let id = reader.value()?;
+        let owner = reader.value()?;
+        Ok(Self::from_parts(
+            reader.value()?,
+            reader.quantized_unsigned(MASS_SCALE)?,
+            owner,
+            id,
+            reader.value()?,
+        ))
+    }
+}
+
+impl Encode for FoodCell {
+    fn encode(&self, writer: &mut Writer) {
+        writer.value(&self.id());
+        writer.value(&self.pos());
+        writer.value(&self.color());
+    }
+}
+
+impl Decode for FoodCell {
+    fn decode(reader: &mut Reader) -> Result<Self, DecodeError> {
+        Ok(Self::from_parts(
+            reader.value()?,
+            reader.value()?,
+            reader.value()?,
+        ))
+    }
+}
+
+impl Encode for EjectedMassCell {
+    fn encode(&self, writer: &mut Writer) {
+        writer.value(&self.pos());
+        writer.value(&self.velocity());
+        writer.value(&self.color());
+    }
+}
+
+impl Decode for EjectedMassCell {
+    fn decode(reader: &mut Reader) -> Result<Self, DecodeError> {
+        Ok(Self::new(reader.value()?, reader.value()?, reader.value()?))
+    }
+}
+
+impl Encode for VirusCell {
+    fn encode(&self, writer: &mut Writer) {
+        writer.value(&self.pos());
+        writer.quantized_unsigned(self.mass(), MASS_SCALE);
+        writer.value(&self.velocity());
+    }
+}
+
+impl Decode for VirusCell {
+    fn decode(reader: &mut Reader) -> Result<Self, DecodeError> {
+        Ok(Self::from_parts(
+            reader.value()?,
+            reader.quantized_unsigned(MASS_SCALE)?,
+            reader.value()?,
+        ))
+    }
+}
+
+const SPLIT_FLAG: u8 = 1;
+const EJECT_FLAG: u8 = 1 << 1;
+const FREEZE_FLAG: u8 = 1 << 2;
+
+impl Encode for PlayerInput {
+    fn encode(&self, writer: &mut Writer) {
+        writer.quantized(self.move_vec.x, INPUT_SCALE);
+        writer.quantized(self.move_vec.y, INPUT_SCALE);
+        let mut flags = 0;
+        if self.split {
+            flags |= SPLIT_FLAG;
+        }
+        if self.eject {
+            flags |= EJECT_FLAG;
+        }
+        if self.freeze {
+            flags |= FREEZE_FLAG;
+        }
+        writer.u8(flags);
+    }
+}
+
+impl Decode for PlayerInput {
+    fn decode(reader: &mut Reader) -> Result<Self, DecodeError> {
+        let move_vec = Vec2 {
+            x: reader.quantized(INPUT_SCALE)?,
+            y: reader.quantized(INPUT_SCALE)?,
+        };
+        let flags = reader.u8()?;
+        if flags & !(SPLIT_FLAG | EJECT_FLAG | FREEZE_FLAG) != 0 {
+            return Err(DecodeError::OutOfRange("input flags"));
+        }
+        Ok(Self {
+            move_vec,
+            split: flags & SPLIT_FLAG != 0,
+            eject: flags & EJECT_FLAG != 0,
+            freeze: flags & FREEZE_FLAG != 0,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip<T: Encode + Decode>(value: &T) -> T {
+        let mut writer = Writer::new();
+        writer.value(value);
+        let bytes = writer.into_bytes();
+        let mut reader = Reader::new(&bytes);
+        let decoded = reader.value().expect("encoded values can be decoded");
+        reader.finish().expect("the whole value is read");
+        decoded
+    }
+
+    fn decode_error<T: Decode>(bytes: &[u8]) -> DecodeError {
+        match Reader::new(bytes).value::<T>() {
+            Ok(_) => panic!("{:?} decoded", bytes),
+            Err(e) => e,
+        }
+    }
+
+    /// Asserts that `actual` is `expected` rounded to a multiple of `1 / scale`
+    fn assert_quantized(actual: f64, expected: f64, scale: f64) {
+        assert!(
+            (actual - expected).abs() <= 0.5 / scale,
+            "{} is not {} to within 1/{}",
+            actual,
+            expected,
+            scale
+        );
+        assert_eq!(actual * scale, (actual * scale).round());
+    }
+
+    fn assert_point(actual: Point, expected: Point) {
+        assert_quantized(actual.x, expected.x, POSITION_SCALE);
+        assert_quantized(actual.y, expected.y, POSITION_SCALE);
+    }
+
+    fn assert_velocity(actual: Vec2, expected: Vec2) {
+        assert_quantized(actual.x, expected.x, VELOCITY_SCALE);
+        assert_quantized(actual.y, expected.y, VELOCITY_SCALE);
+    }
+
+    fn assert_color(actual: HSL, expected: HSL) {
+        assert_eq!(
+            (actual.hue(), actual.saturation(), actual.lightness()),
+            (expected.hue(), expected.saturation(), expected.lightness())
+        );
+    }
+
+    #[test]
+    fn varints_round_trip() {
+        for value in [0, 1, 0x7f, 0x80, 0x3fff, 0x4000, u32::MAX as u64, u64::MAX] {
+            let mut writer = Writer::new();
+            writer.varint(value);
+            let bytes = writer.into_bytes();
+            assert_eq!(Reader::new(&bytes).varint(), Ok(value));
+        }
+        for value in [0, 1, -1, 63, -64, 64, i64::MAX, i64::MIN] {
+            let mut writer = Writer::new();
+            writer.signed_varint(value);
+            let bytes = writer.into_bytes();
+            assert_eq!(Reader::new(&bytes).signed_varint(), Ok(value));
+        }
+    }
+
+    #[test]
+    fn small_signed_varints_take_one_byte() {
+        for value in [-64, -1, 0, 1, 63] {
+            let mut writer = Writer::new();
+            writer.signed_varint(value);
+            assert_eq!(writer.into_bytes().len(), 1);
+        }
+    }
+
+    #[test]
+    fn shapes_round_trip() {
+        let point = Point {
+            x: 123.456,
+            y: -0.03,
+        };
+        assert_point(round_trip(&point), point);
+
+        let velocity = Vec2 { x: -7.77, y: 0.5 };
+        assert_velocity(round_trip(&velocity), velocity);
+
+        let circle = Circle {
+            center: point,
+            radius: 41.2,
+        };
+        let decoded = round_trip(&circle);
+        assert_point(decoded.center, circle.center);
+        assert_quantized(decoded.radius, circle.radius, POSITION_SCALE);
+
+        let rect = Rect {
+            top_left: point,
+            width: 1000.01,
+            height: 600.99,
+        };
+        let decoded = round_trip(&rect);
+        assert_point(decoded.top_left, rect.top_left);
+        assert_quantized(decoded.width, rect.width, POSITION_SCALE);
+        assert_quantized(decoded.height, rect.height, POSITION_SCALE);
+    }
+
+    #[test]
+    fn ids_round_trip() {
+        for id in [0, 1, 300, u32::MAX] {
+            assert_eq!(round_trip(&PlayerId::new(id)).value(), id);
+            assert_eq!(round_trip(&PlayerCellId::new(id)).value(), id);
+            assert_eq!(round_trip(&FoodCellId::new(id)).value(), id);
+        }
+    }
+
+    #[test]
+    fn ids_out_of_range_are_rejected() {
+        let mut writer = Writer::new();
+        writer.varint(u32::MAX as u64 + 1);
+        let bytes = writer.into_bytes();
+        assert_eq!(
+            decode_error::<PlayerId>(&bytes),
+            DecodeError::OutOfRange("player id")
+        );
+    }
+
+    #[test]
+    fn player_info_round_trips() {
+        let info =
+            PlayerInfo::from_parts(PlayerId::new(7), "Größe 🦀".into(), HSL::new(200, 80, 40));
+        let decoded = round_trip(&info);
+        assert_eq!(decoded.id().value(), 7);
+        assert_eq!(decoded.name(), "Größe 🦀");
+        assert_color(decoded.color(), info.color());
+    }
+
+    #[test]
+    fn player_cell_round_trips() {
+        let cell = PlayerCell::from_parts(
+            Point { x: 10.01, y: 20.02 },
+            123.456,
+            PlayerId::new(3),
+            PlayerCellId::new(9),
+            Vec2 { x: 1.001, y: -2.5 },
+        );
+        let decoded = round_trip(&cell);
+        assert_eq!(decoded.id().value(), 9);
+        assert_eq!(decoded.owner().value(), 3);
+        assert_point(decoded.pos(), cell.pos());
+        assert_quantized(decoded.mass(), cell.mass(), MASS_SCALE);
+        assert_velocity(decoded.velocity(), cell.velocity());
+    }
+
+    #[test]
+    fn food_cell_round_trips() {
+        let cell = FoodCell::from_parts(
+            FoodCellId::new(42),
+            Point { x: 5.5, y: 999.97 },
+            HSL::new(1, 2, 3),
+        );
+        let decoded = round_trip(&cell);
+        assert_eq!(decoded.id().value(), 42);
+        assert_point(decoded.pos(), cell.pos());
+        assert_color(decoded.color(), cell.color());
+    }
+
+    #[test]
+    fn player_input_round_trips() {
+        for (split, eject, freeze) in [
+            (false, false, false),
+            (true, false, false),
+            (false, true, false),
+            (false, false, true),
+            (true, true, true),
+        ] {
+            let input = PlayerInput {
+                move_vec: Vec2 {
+                    x: 0.123456,
+                    y: -0.987654,
+                },
+                split,
+                eject,
+                freeze,
+            };
+            let decoded = round_trip(&input);
+            assert_quantized(decoded.move_vec.x, input.move_vec.x, INPUT_SCALE);
+            assert_quantized(decoded.move_vec.y, input.move_vec.y, INPUT_SCALE);
+            assert_eq!(
+                (decoded.split, decoded.eject, decoded.freeze),
+                (split, eject, freeze)
+            );
+        }
+    }
+
+    #[test]
+    fn unknown_input_flags_are_rejected() {
+        let mut writer = Writer::new();
+        writer.value(&PlayerInput::default());
+        let mut bytes = writer.into_bytes();
+        *bytes.last_mut().unwrap() = 1 << 3;
+        assert_eq!(
+            decode_error::<PlayerInput>(&bytes),
+            DecodeError::OutOfRange("input flags")
+        );
+    }
+
+    #[test]
+    fn eleven_byte_varints_overflow() {
+        let mut bytes = vec![0x80; 10];
+        bytes.push(0);
+        assert_eq!(
+            Reader::new(&bytes).varint(),
+            Err(DecodeError::VarintOverflow)
+        );
+    }
+
+    #[test]
+    fn varints_past_u64_overflow() {
+        let mut bytes = vec![0xff; 9];
+        bytes.push(0x02);
+        assert_eq!(
+            Reader::new(&bytes).varint(),
+            Err(DecodeError::VarintOverflow)
+        );
+    }
+
+    #[test]
+    fn truncated_varints_end_unexpectedly() {
+        assert_eq!(
+            Reader::new(&[0x80, 0x80]).varint(),
+            Err(DecodeError::UnexpectedEnd)
+        );
+    }
+
+    #[test]
+    fn invalid_utf8_is_rejected() {
+        assert_eq!(
+            Reader::new(&[2, 0xff, 0xfe]).string(),
+            Err(DecodeError::InvalidUtf8)
+        );
+    }
+
+    #[test]
+    fn lengths_longer_than_the_frame_are_rejected() {
+        assert_eq!(
+            Reader::new(&[5, b'a', b'b']).string(),
+            Err(DecodeError::UnexpectedEnd)
+        );
+
+        let mut writer = Writer::new();
+        writer.varint(u64::MAX);
+        writer.value(&PlayerId::new(1));
+        let bytes = writer.into_bytes();
+        assert_eq!(
+            Reader::new(&bytes).list::<PlayerId>().err(),
+            Some(DecodeError::UnexpectedEnd)
+        );
+    }
+
+    #[test]
+    fn unread_bytes_are_reported() {
+        let mut reader = Reader::new(&[1, 2, 3]);
+        reader.u8().unwrap();
+        assert_eq!(reader.finish(), Err(DecodeError::TrailingBytes(2)));
+    }
+}
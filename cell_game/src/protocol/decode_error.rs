@@ -0,0 +1,38 @@
+use std::{error::Error, fmt};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DecodeError {
+    /// The frame ended in the middle of a value
+    UnexpectedEnd,
+    /// A varint was longer than the largest value it could hold
+    VarintOverflow,
+    /// A value was out of the range of the type it's decoded into
+    OutOfRange(&'static str),
+    /// A string wasn't valid UTF-8
+    InvalidUtf8,
+    /// A message started with a tag that doesn't belong to any message
+    UnknownMessage(u8),
+    /// The frame had bytes left over after its message
+    TrailingBytes(usize),
+    /// A view delta was relative to a frame that the client doesn't have
+    UnknownBaseline(u32),
+    /// A view delta added a cell without sending all of its fields
+    IncompleteCell,
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnexpectedEnd => write!(f, "frame ended unexpectedly"),
+            Self::VarintOverflow => write!(f, "varint is too long"),
+            Self::OutOfRange(field) => write!(f, "{} is out of range", field),
+            Self::InvalidUtf8 => write!(f, "string is not valid UTF-8"),
+            Self::UnknownMessage(tag) => write!(f, "unknown message tag {}", tag),
+            Self::TrailingBytes(len) => write!(f, "{} unread bytes after message", len),
+            Self::UnknownBaseline(frame) => write!(f, "unknown baseline frame {}", frame),
+            Self::IncompleteCell => write!(f, "new cell is missing fields"),
+        }
+    }
+}
+
+impl Error for DecodeError {}
@@ -0,0 +1,742 @@
+use std::collections::{BTreeMap, VecDeque};
+
+use crate::{
+    cells::{
+        cell::Cell, ejected_mass_cell::EjectedMassCell, food_cell::FoodCell,
+        player_cell::PlayerCell, virus_cell::VirusCell,
+    },
+    game_view::GameView,
+    ids::{FoodCellId, PlayerCellId, PlayerId},
+    leaderboard::LeaderboardEntry,
+    player_info::PlayerInfo,
+    pos::{Circle, Point, Rect, Vec2},
+};
+
+use super::{
+    codec::{Decode, Encode, Reader, Writer, MASS_SCALE},
+    decode_error::DecodeError,
+    messages::ViewFrame,
+};
+
+/// How many of the most recent views are kept to be used as baselines. If a
+/// client falls further behind than this, it's sent a full view instead.
+const BASELINE_WINDOW: usize = 64;
+
+/// The changes between a view the client already has and the current view.
+/// Ejected mass, viruses and the leaderboard are small and change often, so
+/// they are always sent in full, as are the world bounds.
+pub struct ViewDelta {
+    /// The number of this view. Clients acknowledge views by this number.
+    pub frame: u32,
+    /// The frame that this delta is relative to, or `None` if the delta
+    /// holds the full view
+    pub baseline: Option<u32>,
+    /// The sequence number of the last input the server applied before this
+    /// view, or 0 if it hasn't applied any. [`ViewEncoder`] leaves this as 0
+    /// for the connection to fill in.
+    pub input_ack: u32,
+    pub owner: PlayerId,
+    pub view_area: Circle,
+    pub world_bounds: Rect,
+    /// Player cells that entered the view or changed since the baseline
+    pub player_cells: Vec<PlayerCellUpdate>,
+    pub removed_player_cells: Vec<PlayerCellId>,
+    /// Food never changes once spawned, so only new food is sent
+    pub food_cells: Vec<FoodCell>,
+    pub removed_food_cells: Vec<FoodCellId>,
+    pub ejected_mass_cells: Vec<EjectedMassCell>,
+    pub virus_cells: Vec<VirusCell>,
+    pub player_infos: Vec<PlayerInfo>,
+    pub removed_player_infos: Vec<PlayerId>,
+    pub leaderboard: Vec<LeaderboardEntry>,
+}
+
+/// The fields of a player cell that changed since the baseline. Cells that
+/// entered the view have every field set.
+pub struct PlayerCellUpdate {
+    pub id: PlayerCellId,
+    pub owner: Option<PlayerId>,
+    pub pos: Option<Point>,
+    pub mass: Option<f64>,
+    pub velocity: Option<Vec2>,
+}
+
+/// Tracks the views sent to a single client so that each new view can be sent
+/// as a delta against the last view the client acknowledged
+pub struct ViewEncoder {
+    next_frame: u32,
+    sent: VecDeque<(u32, Baseline)>,
+    acknowledged: Option<u32>,
+}
+
+/// Rebuilds full views from the deltas sent by a [`ViewEncoder`]
+pub struct ViewDecoder {
+    received: VecDeque<(u32, Baseline)>,
+}
+
+/// The cells of a view that are sent as changes, keyed by their ids. Cells are
+/// stored exactly as the client decodes them, so that the server and client
+/// compare against identical baselines.
+#[derive(Clone, Default)]
+struct Baseline {
+    player_cells: BTreeMap<PlayerCellId, PlayerCell>,
+    food_cells: BTreeMap<FoodCellId, FoodCell>,
+    player_infos: BTreeMap<PlayerId, PlayerInfo>,
+}
+
+impl ViewEncoder {
+    pub fn new() -> Self {
+        Self {
+            // Frames start at 1 so that 0 can mean "no baseline" on the wire
+            next_frame: 1,
+            sent: VecDeque::new(),
+            acknowledged: None,
+        }
+    }
+
+    /// Marks a frame as received by the client. Later deltas are relative to
+    /// the most recent acknowledged frame.
+    pub fn acknowledge(&mut self, frame: u32) {
+        if self.acknowledged.is_some_and(|acked| acked >= frame)
+            || !self.sent.iter().any(|(sent, _)| *sent == frame)
+        {
+            return;
+        }
+        self.acknowledged = Some(frame);
+        // Acknowledgements only move forward, so older frames are never used
+        // as baselines again
+        self.sent.retain(|(sent, _)| *sent >= frame);
+    }
+
+    pub fn encode(&mut self, view: &impl GameView) -> ViewDelta {
+        let current = Baseline::from_view(view);
+        let empty = Baseline::default();
+        let (baseline, prev) = match self
+            .acknowledged
+            .and_then(|acked| self.sent.iter().find(|(sent, _)| *sent == acked))
+        {
+            Some((frame, prev)) => (Some(*frame), prev),
+            None => (None, &empty),
+        };
+
+        let delta = ViewDelta {
+            frame: self.next_frame,
+            baseline,
+            input_ack: 0,
+            owner: view.owner(),
+            view_area: quantized(&view.view_area()),
+            world_bounds: view.world_bounds(),
+            player_cells: current
+                .player_cells
+                .values()
+                .filter_map(|cell| {
+                    PlayerCellUpdate::between(prev.player_cells.get(&cell.id()), cell)
+                })
+                .collect(),
+            removed_player_cells: removed(&prev.player_cells, &current.player_cells),
+            food_cells: current
+                .food_cells
+                .iter()
+                .filter(|(id, _)| !prev.food_cells.contains_key(id))
+                .map(|(_, cell)| cell.clone())
+                .collect(),
+            removed_food_cells: removed(&prev.food_cells, &current.food_cells),
+            ejected_mass_cells: view.ejected_mass_cells().collect(),
+            virus_cells: view.virus_cells().collect(),
+            player_infos: current
+                .player_infos
+                .iter()
+                .filter(|(id, _)| !prev.player_infos.contains_key(id))
+                .map(|(_, info)| info.clone())
+                .collect(),
+            removed_player_infos: removed(&prev.player_infos, &current.player_infos),
+            leaderboard: view.leaderboard().collect(),
+        };
+
+        self.sent.push_back((self.next_frame, current));
+        if self.sent.len() > BASELINE_WINDOW {
+            self.sent.pop_front();
+        }
+        self.next_frame += 1;
+        delta
+    }
+}
+
+impl Default for ViewEncoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ViewDecoder {
+    pub fn new() -> Self {
+        Self {
+            received: VecDeque::new(),
+        }
+    }
+
+    /// Applies a delta to the baseline it was encoded against, returning the
+    /// full view. The client should then acknowledge `delta.frame`.
+    pub fn decode(&mut self, delta: ViewDelta) -> Result<ViewFrame, DecodeError> {
+        let mut view = match delta.baseline {
+            Some(baseline) => self
+                .received
+                .iter()
+                .find(|(frame, _)| *frame == baseline)
+                .map(|(_, view)| view.clone())
+                .ok_or(DecodeError::UnknownBaseline(baseline))?,
+            None => Baseline::default(),
+        };
+
+        for id in delta.removed_player_cells {
+            view.player_cells.remove(&id);
+        }
+        for update in delta.player_cells {
+            let cell = update.apply(view.player_cells.get(&update.id))?;
+            view.player_cells.insert(update.id, cell);
+        }
+        for id in delta.removed_food_cells {
+            view.food_cells.remove(&id);
+        }
+        for cell in delta.food_cells {
+            view.food_cells.insert(cell.id(), cell);
+        }
+        for id in delta.removed_player_infos {
+            view.player_infos.remove(&id);
+        }
+        for info in delta.player_infos {
+            view.player_infos.insert(info.id(), info);
+        }
+
+        let frame = ViewFrame {
+            owner: delta.owner,
+            view_area: delta.view_area,
+            world_bounds: delta.world_bounds,
+            player_cells: view.player_cells.values().cloned().collect(),
+            food_cells: view.food_cells.values().cloned().collect(),
+            ejected_mass_cells: delta.ejected_mass_cells,
+            virus_cells: delta.virus_cells,
+            player_infos: view.player_infos.values().cloned().collect(),
+            leaderboard: delta.leaderboard,
+        };
+
+        // The server never goes back to a baseline older than the one it just
+        // used, so only newer views need to be kept
+        if let Some(baseline) = delta.baseline {
+            self.received.retain(|(frame, _)| *frame >= baseline);
+        }
+        self.received.push_back((delta.frame, view));
+        if self.received.len() > BASELINE_WINDOW {
+            self.received.pop_front();
+        }
+        Ok(frame)
+    }
+}
+
+impl Default for ViewDecoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Baseline {
+    fn from_view(view: &impl GameView) -> Self {
+        Self {
+            player_cells: view
+                .player_cells()
+                .map(|cell| (cell.id(), quantized(&cell)))
+                .collect(),
+            food_cells: view
+                .food_cells()
+                .map(|cell| (cell.id(), quantized(&cell)))
+                .collect(),
+            player_infos: view
+                .player_infos()
+                .map(|info| (info.id(), info.clone()))
+                .collect(),
+        }
+    }
+}
+
+impl PlayerCellUpdate {
+    const OWNER: u8 = 1;
+    const POS: u8 = 1 << 1;
+    const MASS: u8 = 1 << 2;
+    const VELOCITY: u8 = 1 << 3;
+
+    /// The update that turns `prev` into `curr`, or `None` if nothing changed
+    fn between(prev: Option<&PlayerCell>, curr: &PlayerCell) -> Option<Self> {
+        let update = Self {
+            id: curr.id(),
+            owner: prev.is_none().then(|| curr.owner()),
+            pos: changed(prev.map(|cell| cell.pos()), curr.pos()),
+            mass: changed(prev.map(|cell| cell.mass()), curr.mass()),
+            velocity: changed(prev.map(|cell| cell.velocity()), curr.velocity()),
+        };
+        (update.owner.is_some()
+            || update.pos.is_some()
+            || update.mass.is_some()
+            || update.velocity.is_some())
+        .then_some(update)
+    }
+
+    fn apply(&self, prev: Option<&PlayerCell>) -> Result<PlayerCell, DecodeError> {
+        let (owner, pos, mass, velocity) = match prev {
+            Some(prev) => (
+                prev.owner(),
+                self.pos.unwrap_or(prev.pos()),
+                self.mass.unwrap_or(prev.mass()),
+                self.velocity.unwrap_or(prev.velocity()),
+            ),
+            None => (
+                self.owner.ok_or(DecodeError::IncompleteCell)?,
+                self.pos.ok_or(DecodeError::IncompleteCell)?,
+                self.mass.ok_or(DecodeError::IncompleteCell)?,
+                self.velocity.ok_or(DecodeError::IncompleteCell)?,
+            ),
+        };
+        Ok(PlayerCell::from_parts(pos, mass, owner, self.id, velocity))
+    }
+}
+
+/// `curr` if it differs from `prev` or there is no `prev`
+fn changed<T: PartialEq>(prev: Option<T>, curr: T) -> Option<T> {
+    match prev {
+        Some(prev) if prev == curr => None,
+        _ => Some(curr),
+    }
+}
+
+/// The keys of `prev` that aren't in `curr`
+fn removed<K: Ord + Copy, V>(prev: &BTreeMap<K, V>, curr: &BTreeMap<K, V>) -> Vec<K> {
+    prev.keys()
+        .filter(|id| !curr.contains_key(id))
+        .copied()
+        .collect()
+}
+
+/// Rounds a value the same way sending it to a client would
+fn quantized<T: Encode + Decode>(value: &T) -> T {
+    let mut writer = Writer::new();
+    writer.value(value);
+    Reader::new(&writer.into_bytes())
+        .value()
+        .expect("encoded values can always be decoded")
+}
+
+impl Encode for ViewDelta {
+    fn encode(&self, writer: &mut Writer) {
+        writer.varint(self.frame as u64);
+        writer.varint(self.baseline.unwrap_or(0) as u64);
+        writer.varint(self.input_ack as u64);
+        writer.value(&self.owner);
+        writer.value(&self.view_area);
+        writer.value(&self.world_bounds);
+        writer.list(self.player_cells.iter());
+        writer.list(self.removed_player_cells.iter());
+        writer.list(self.food_cells.iter());
+        writer.list(self.removed_food_cells.iter());
+        writer.list(self.ejected_mass_cells.iter());
+        writer.list(self.virus_cells.iter());
+        writer.list(self.player_infos.iter());
+        writer.list(self.removed_player_infos.iter());
+        writer.list(self.leaderboard.iter());
+    }
+}
+
+impl Decode for ViewDelta {
+    fn decode(reader: &mut Reader) -> Result<Self, DecodeError> {
+        Ok(Self {
+            frame: reader.u32("frame")?,
+            baseline: Some(reader.u32("baseline frame")?).filter(|&frame| frame != 0),
+            input_ack: reader.u32("input sequence number")?,
+            owner: reader.value()?,
+            view_area: reader.value()?,
+            world_bounds: reader.value()?,
+            player_cells: reader.list()?,
+            removed_player_cells: reader.list()?,
+            food_cells: reader.list()?,
+            removed_food_cells: reader.list()?,
+            ejected_mass_cells: reader.list()?,
+            virus_cells: reader.list()?,
+            player_infos: reader.list()?,
+            removed_player_infos: reader.list()?,
+            leaderboard: reader.list()?,
+        })
+    }
+}
+
+impl Encode for PlayerCellUpdate {
+    fn encode(&self, writer: &mut Writer) {
+        writer.value(&self.id);
+        let mut flags = 0;
+        for (present, flag) in [
+            (self.owner.is_some(), Self::OWNER),
+            (self.pos.is_some(), Self::POS),
+            (self.mass.is_some(), Self::MASS),
+            (self.velocity.is_some(), Self::VELOCITY),
+        ] {
+            if present {
+                flags |= flag;
+            }
+        }
+        writer.u8(flags);
+        if let Some(owner) = &self.owner {
+            writer.value(owner);
+        }
+        if let Some(pos) = &self.pos {
+            writer.value(pos);
+        }
+        if let Some(mass) = self.mass {
+            writer.quantized_unsigned(mass, MASS_SCALE);
+        }
+        if let Some(velocity) = &self.velocity {
+            writer.value(velocity);
+        }
+    }
+}
+
+impl Decode for PlayerCellUpdate {
+    fn decode(reader: &mut Reader) -> Result<Self, DecodeError> {
+        let id = reader.value()?;
+        let flags = reader.u8()?;
+        if flags & !(Self::OWNER | Self::POS | Self::MASS | Self::VELOCITY) != 0 {
+            return Err(DecodeError::OutOfRange("player cell update flags"));
+        }
+        let has = |flag| flags & flag != 0;
+        Ok(Self {
+            id,
+            owner: has(Self::OWNER).then(|| reader.value()).transpose()?,
+            pos: has(Self::POS).then(|| reader.value()).transpose()?,
+            mass: has(Self::MASS)
+                .then(|| reader.quantized_unsigned(MASS_SCALE))
+                .transpose()?,
+            velocity: has(Self::VELOCITY).then(|| reader.value()).transpose()?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{color::HSL, ids::Id};
+
+    use super::*;
+
+    fn player_cell(id: u32, owner: u32, x: f64, y: f64, mass: f64) -> PlayerCell {
+        PlayerCell::from_parts(
+            Point { x, y },
+            mass,
+            PlayerId::new(owner),
+            PlayerCellId::new(id),
+            Vec2 {
+                x: x / 7.0,
+                y: -y / 9.0,
+            },
+        )
+    }
+
+    fn food_cell(id: u32, x: f64, y: f64) -> FoodCell {
+        FoodCell::from_parts(
+            FoodCellId::new(id),
+            Point { x, y },
+            HSL::new(id as u8, 50, 50),
+        )
+    }
+
+    fn player_info(id: u32) -> PlayerInfo {
+        PlayerInfo::from_parts(
+            PlayerId::new(id),
+            format!("Player {}", id),
+            HSL::new(0, 0, 0),
+        )
+    }
+
+    fn view(
+        player_cells: Vec<PlayerCell>,
+        food_cells: Vec<FoodCell>,
+        player_infos: Vec<PlayerInfo>,
+    ) -> ViewFrame {
+        let center = player_cells.first().map_or(
+            Point {
+                x: 500.01,
+                y: 500.01,
+            },
+            |cell| cell.pos(),
+        );
+        ViewFrame {
+            owner: PlayerId::new(1),
+            view_area: Circle {
+                center,
+                radius: 333.333,
+            },
+            world_bounds: Rect {
+                top_left: Point { x: 0.0, y: 0.0 },
+                width: 1000.0,
+                height: 1000.0,
+            },
+            player_cells,
+            food_cells,
+            ejected_mass_cells: vec![EjectedMassCell::new(
+                Point { x: 1.01, y: 2.02 },
+                Vec2 { x: 3.03, y: 4.04 },
+                HSL::new(1, 2, 3),
+            )],
+            virus_cells: vec![VirusCell::from_parts(
+                Point { x: 5.05, y: 6.06 },
+                100.001,
+                Vec2::ZERO,
+            )],
+            player_infos,
+            leaderboard: vec![LeaderboardEntry {
+                rank: 1,
+                player: PlayerId::new(1),
+                mass: 123.456,
+            }],
+        }
+    }
+
+    /// Sends a delta over the wire
+    fn send(delta: &ViewDelta) -> ViewDelta {
+        let mut writer = Writer::new();
+        writer.value(delta);
+        let bytes = writer.into_bytes();
+        let mut reader = Reader::new(&bytes);
+        let delta = reader.value().unwrap();
+        reader.finish().unwrap();
+        delta
+    }
+
+    /// Asserts that a decoded view holds exactly what a client would see if
+    /// it were sent `source` in full
+    fn assert_view(decoded: &ViewFrame, source: &ViewFrame) {
+        assert!(decoded.owner == source.owner);
+        assert!(decoded.view_area == quantized(&source.view_area));
+        assert!(decoded.world_bounds == quantized(&source.world_bounds));
+
+        let mut player_cells: Vec<_> = source.player_cells.iter().map(quantized).collect();
+        player_cells.sort_by_key(|cell| cell.id());
+        assert_eq!(decoded.player_cells.len(), player_cells.len());
+        for (decoded, expected) in decoded.player_cells.iter().zip(&player_cells) {
+            assert_eq!(decoded.id().value(), expected.id().value());
+            assert!(decoded.owner() == expected.owner());
+            assert!(decoded.pos() == expected.pos());
+            assert_eq!(decoded.mass(), expected.mass());
+            assert!(decoded.velocity() == expected.velocity());
+        }
+
+        let mut food_cells: Vec<_> = source.food_cells.iter().map(quantized).collect();
+        food_cells.sort_by_key(|cell| cell.id());
+        assert_eq!(decoded.food_cells.len(), food_cells.len());
+        for (decoded, expected) in decoded.food_cells.iter().zip(&food_cells) {
+            assert_eq!(decoded.id().value(), expected.id().value());
+            assert!(decoded.pos() == expected.pos());
+            assert_eq!(decoded.color().hue(), expected.color().hue());
+        }
+
+        assert_eq!(decoded.ejected_mass_cells.len(), 1);
+        let ejected = quantized(&source.ejected_mass_cells[0]);
+        assert!(decoded.ejected_mass_cells[0].pos() == ejected.pos());
+        assert!(decoded.ejected_mass_cells[0].velocity() == ejected.velocity());
+
+        assert_eq!(decoded.virus_cells.len(), 1);
+        let virus = quantized(&source.virus_cells[0]);
+        assert!(decoded.virus_cells[0].pos() == virus.pos());
+        assert_eq!(decoded.virus_cells[0].mass(), virus.mass());
+
+        let mut player_infos: Vec<_> = source.player_infos.clone();
+        player_infos.sort_by_key(|info| info.id());
+        assert_eq!(decoded.player_infos.len(), player_infos.len());
+        for (decoded, expected) in decoded.player_infos.iter().zip(&player_infos) {
+            assert!(decoded.id() == expected.id());
+            assert_eq!(decoded.name(), expected.name());
+        }
+
+        let leaderboard: Vec<_> = source.leaderboard.iter().map(quantized).collect();
+        assert!(decoded.leaderboard == leaderboard);
+    }
+
+    /// Encodes `source`, checks that the client rebuilds it exactly, and
+    /// returns the delta that was sent
+    fn step(encoder: &mut ViewEncoder, decoder: &mut ViewDecoder, source: &ViewFrame) -> ViewDelta {
+        let delta = encoder.encode(source);
+        let decoded = decoder.decode(send(&delta)).unwrap();
+        assert_view(&decoded, source);
+        delta
+    }
+
+    fn ids<T: Id + Copy>(ids: &[T]) -> Vec<u32> {
+        ids.iter().map(|&id| id.value()).collect()
+    }
+
+    #[test]
+    fn views_are_rebuilt_from_deltas() {
+        let mut encoder = ViewEncoder::new();
+        let mut decoder = ViewDecoder::new();
+
+        let first = view(
+            vec![
+                player_cell(1, 1, 100.01, 100.02, 50.005),
+                player_cell(2, 2, 200.03, 200.04, 60.006),
+            ],
+            vec![food_cell(1, 10.1, 10.2), food_cell(2, 20.1, 20.2)],
+            vec![player_info(1), player_info(2)],
+        );
+        let delta = step(&mut encoder, &mut decoder, &first);
+        assert_eq!((delta.frame, delta.baseline), (1, None));
+        assert_eq!(delta.player_cells.len(), 2);
+        assert_eq!(delta.food_cells.len(), 2);
+        assert_eq!(delta.player_infos.len(), 2);
+        encoder.acknowledge(1);
+
+        // Cell 1 moves, cell 2 gains mass, cell 3 enters and food 1 is eaten
+        let second = view(
+            vec![
+                player_cell(1, 1, 110.01, 105.02, 50.005),
+                player_cell(2, 2, 200.03, 200.04, 70.007),
+                player_cell(3, 3, 300.05, 300.06, 10.001),
+            ],
+            vec![food_cell(2, 20.1, 20.2)],
+            vec![player_info(1), player_info(2), player_info(3)],
+        );
+        let delta = step(&mut encoder, &mut decoder, &second);
+        assert_eq!((delta.frame, delta.baseline), (2, Some(1)));
+        let updates: Vec<_> = delta
+            .player_cells
+            .iter()
+            .map(|update| {
+                (
+                    update.id.value(),
+                    update.owner.is_some(),
+                    update.pos.is_some(),
+                    update.mass.is_some(),
+                    update.velocity.is_some(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            updates,
+            [
+                (1, false, true, false, true),
+                (2, false, false, true, false),
+                (3, true, true, true, true),
+            ]
+        );
+        assert!(delta.food_cells.is_empty());
+        assert_eq!(ids(&delta.removed_food_cells), [1]);
+        assert_eq!(delta.player_infos.len(), 1);
+        encoder.acknowledge(2);
+
+        // Cell 2 leaves along with its player, a new pellet spawns and a cell
+        // that didn't change isn't sent
+        let third = view(
+            vec![
+                player_cell(1, 1, 110.01, 105.02, 50.005),
+                player_cell(3, 3, 300.05, 300.06, 10.001),
+            ],
+            vec![food_cell(2, 20.1, 20.2), food_cell(3, 10.1, 10.2)],
+            vec![player_info(1), player_info(3)],
+        );
+        let delta = step(&mut encoder, &mut decoder, &third);
+        assert_eq!((delta.frame, delta.baseline), (3, Some(2)));
+        assert!(delta.player_cells.is_empty());
+        assert_eq!(ids(&delta.removed_player_cells), [2]);
+        assert_eq!(delta.food_cells.len(), 1);
+        assert_eq!(delta.food_cells[0].id().value(), 3);
+        assert!(delta.removed_food_cells.is_empty());
+        assert!(delta.player_infos.is_empty());
+        assert_eq!(ids(&delta.removed_player_infos), [2]);
+    }
+
+    #[test]
+    fn deltas_use_the_last_acknowledged_frame() {
+        let mut encoder = ViewEncoder::new();
+        let mut decoder = ViewDecoder::new();
+        let views: Vec<_> = (0..6)
+            .map(|i| {
+                view(
+                    vec![player_cell(1, 1, 100.0 + i as f64, 100.0, 50.0)],
+                    (0..=i).map(|id| food_cell(id, id as f64, 0.0)).collect(),
+                    vec![player_info(1)],
+                )
+            })
+            .collect();
+
+        step(&mut encoder, &mut decoder, &views[0]);
+        encoder.acknowledge(1);
+        for source in &views[1..5] {
+            let delta = step(&mut encoder, &mut decoder, source);
+            assert_eq!(delta.baseline, Some(1));
+        }
+
+        // The ack for frame 3 arrives after frame 5 was sent
+        encoder.acknowledge(3);
+        let delta = step(&mut encoder, &mut decoder, &views[5]);
+        assert_eq!((delta.frame, delta.baseline), (6, Some(3)));
+        assert_eq!(delta.food_cells.len(), 3);
+
+        // Acks older than the current baseline are ignored
+        encoder.acknowledge(2);
+        let delta = step(&mut encoder, &mut decoder, &views[5]);
+        assert_eq!((delta.frame, delta.baseline), (7, Some(3)));
+    }
+
+    #[test]
+    fn full_views_are_sent_once_the_baseline_is_too_old() {
+        let mut encoder = ViewEncoder::new();
+        let mut decoder = ViewDecoder::new();
+        let source = view(
+            vec![player_cell(1, 1, 100.0, 100.0, 50.0)],
+            vec![food_cell(1, 10.0, 10.0)],
+            vec![player_info(1)],
+        );
+
+        step(&mut encoder, &mut decoder, &source);
+        encoder.acknowledge(1);
+        for frame in 2..=BASELINE_WINDOW as u32 + 1 {
+            let delta = step(&mut encoder, &mut decoder, &source);
+            assert_eq!((delta.frame, delta.baseline), (frame, Some(1)));
+        }
+
+        let delta = step(&mut encoder, &mut decoder, &source);
+        assert_eq!(delta.baseline, None);
+        assert_eq!(delta.player_cells.len(), 1);
+        assert_eq!(delta.food_cells.len(), 1);
+        assert_eq!(delta.player_infos.len(), 1);
+
+        // Frame 1 is gone, so acknowledging it again changes nothing
+        encoder.acknowledge(1);
+        assert_eq!(step(&mut encoder, &mut decoder, &source).baseline, None);
+    }
+
+    #[test]
+    fn deltas_against_missing_frames_are_rejected() {
+        let mut encoder = ViewEncoder::new();
+        let source = view(Vec::new(), Vec::new(), vec![player_info(1)]);
+        encoder.encode(&source);
+        encoder.acknowledge(1);
+        let delta = encoder.encode(&source);
+
+        let mut decoder = ViewDecoder::new();
+        assert_eq!(
+            decoder.decode(send(&delta)).err(),
+            Some(DecodeError::UnknownBaseline(1))
+        );
+    }
+
+    #[test]
+    fn new_cells_without_every_field_are_rejected() {
+        let mut encoder = ViewEncoder::new();
+        let mut delta = encoder.encode(&view(
+            vec![player_cell(1, 1, 100.0, 100.0, 50.0)],
+            Vec::new(),
+            Vec::new(),
+        ));
+        delta.player_cells[0].velocity = None;
+
+        let mut decoder = ViewDecoder::new();
+        assert_eq!(
+            decoder.decode(send(&delta)).err(),
+            Some(DecodeError::IncompleteCell)
+        );
+    }
+}
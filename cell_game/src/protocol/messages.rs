@@ -0,0 +1,516 @@
+use crate::{
+    cells::{
+        ejected_mass_cell::EjectedMassCell, food_cell::FoodCell, player_cell::PlayerCell,
+        virus_cell::VirusCell,
+    },
+    client_connection::PlayerInput,
+    game_view::GameView,
+    ids::PlayerId,
+    leaderboard::LeaderboardEntry,
+    player_info::PlayerInfo,
+    pos::{Circle, Rect},
+};
+
+use super::{
+    codec::{Decode, Encode, Reader, Writer},
+    decode_error::DecodeError,
+    delta::ViewDelta,
+};
+
+/// A message sent from a client to a server
+pub enum ClientMessage {
+    /// The first message of every connection
+    Hello { version: u16, name: String },
+    /// The player's input for the next tick. Sequence numbers start at 1 and
+    /// go up by one for each input, so the server can tell the client which
+    /// inputs a view already includes.
+    Input { seq: u32, input: PlayerInput },
+    /// Tells the server that a view was received, so that later views can be
+    /// sent relative to it
+    Ack(u32),
+}
+
+/// A message sent from a server to a client
+pub enum ServerMessage {
+    /// Accepts a client's hello and tells them which player they control
+    Welcome { version: u16, player: PlayerId },
+    /// Rejects a client's hello because it speaks a different protocol
+    /// version. The server closes the connection after sending this.
+    VersionMismatch { version: u16 },
+    /// What changed in the player's view this tick
+    View(Box<ViewDelta>),
+}
+
+/// The full contents of a view, as rebuilt by a client
+#[derive(Clone)]
+pub struct ViewFrame {
+    pub owner: PlayerId,
+    pub view_area: Circle,
+    pub world_bounds: Rect,
+    pub player_cells: Vec<PlayerCell>,
+    pub food_cells: Vec<FoodCell>,
+    pub ejected_mass_cells: Vec<EjectedMassCell>,
+    pub virus_cells: Vec<VirusCell>,
+    pub player_infos: Vec<PlayerInfo>,
+    pub leaderboard: Vec<LeaderboardEntry>,
+}
+
+impl ClientMessage {
+    const HELLO: u8 = 0;
+    const INPUT: u8 = 1;
+    const ACK: u8 = 2;
+
+    pub fn encode(&self) -> Vec<u8> {
+        let mut writer = Writer::new();
+        writer.value(self);
+        writer.into_bytes()
+    }
+
+    /// Decodes a whole frame, which must hold exactly one message
+    pub fn decode(frame: &[u8]) -> Result<Self, DecodeError> {
+        let mut reader = Reader::new(frame);
+        let message = reader.value()?;
+        reader.finish()?;
+        Ok(message)
+    }
+}
+
+impl Encode for ClientMessage {
+    fn encode(&self, writer: &mut Writer) {
+        match self {
+            Self::Hello { version, name } => {
+                writer.u8(Self::HELLO);
+                writer.varint(*version as u64);
+                writer.string(name);
+            }
+            Self::Input { seq, input } => {
+                writer.u8(Self::INPUT);
+                writer.varint(*seq as u64);
+                writer.value(input);
+            }
+            Self::Ack(frame) => {
+                writer.u8(Self::ACK);
+                writer.varint(*frame as u64);
+            }
+        }
+    }
+}
+
+impl Decode for ClientMessage {
+    fn decode(reader: &mut Reader) -> Result<Self, DecodeError> {
+        match reader.u8()? {
+            Self::HELLO => Ok(Self::Hello {
+                version: reader.u16("protocol version")?,
+                name: reader.string()?,
+            }),
+            Self::INPUT => Ok(Self::Input {
+                seq: reader.u32("input sequence number")?,
+                input: reader.value()?,
+            }),
+            Self::ACK => Ok(Self::Ack(reader.u32("frame")?)),
+            tag => Err(DecodeError::UnknownMessage(tag)),
+        }
+    }
+}
+
+impl ServerMessage {
+    const WELCOME: u8 = 0;
+    const VERSION_MISMATCH: u8 = 1;
+    const VIEW: u8 = 2;
+
+    pub fn encode(&self) -> Vec<u8> {
+        let mut writer = Writer::new();
+        writer.value(self);
+        writer.into_bytes()
+    }
+
+    /// Decodes a whole frame, which must hold exactly one message
+    pub fn decode(frame: &[u8]) -> Result<Self, DecodeError> {
+        let mut reader = Reader::new(frame);
+        let message = reader.value()?;
+        reader.finish()?;
+        Ok(message)
+    }
+}
+
+impl Encode for ServerMessage {
+    fn encode(&self, writer: &mut Writer) {
+        match self {
+            Self::Welcome { version, player } => {
+                writer.u8(Self::WELCOME);
+                writer.varint(*version as u64);
+                writer.value(player);
+            }
+            Self::VersionMismatch { version } => {
+                writer.u8(Self::VERSION_MISMATCH);
+                writer.varint(*version as u64);
+            }
+            Self::View(delta) => {
+                writer.u8(Self::VIEW);
+                writer.value(delta.as_ref());
+            }
+        }
+    }
+}
+
+impl Decode for ServerMessage {
+    fn decode(reader: &mut Reader) -> Result<Self, DecodeError> {
+        match reader.u8()? {
+            Self::WELCOME => Ok(Self::Welcome {
+                version: reader.u16("protocol version")?,
+                player: reader.value()?,
+            }),
+            Self::VERSION_MISMATCH => Ok(Self::VersionMismatch {
+                version: reader.u16("protocol version")?,
+            }),
+            Self::VIEW => Ok(Self::View(Box::new(reader.value()?))),
+            tag => Err(DecodeError::UnknownMessage(tag)),
+        }
+    }
+}
+
+impl ViewFrame {
+    pub fn new(view: &impl GameView) -> Self {
+        Self {
+            owner: view.owner(),
+            view_area: view.view_area(),
+            world_bounds: view.world_bounds(),
+            player_cells: view.player_cells().collect(),
+            food_cells: view.food_cells().collect(),
+            ejected_mass_cells: view.ejected_mass_cells().collect(),
+            virus_cells: view.virus_cells().collect(),
+            player_infos: view.player_infos().cloned().collect(),
+            leaderboard: view.leaderboard().collect(),
+        }
+    }
+}
+
+impl GameView for ViewFrame {
+    fn player_cells(&self) -> impl Iterator<Item = PlayerCell> {
+        self.player_cells.iter().cloned()
+    }
+
+    fn food_cells(&self) -> impl Iterator<Item = FoodCell> {
+        self.food_cells.iter().cloned()
+    }
+
+    fn ejected_mass_cells(&self) -> impl Iterator<Item = EjectedMassCell> {
+        self.ejected_mass_cells.iter().cloned()
+    }
+
+    fn virus_cells(&self) -> impl Iterator<Item = VirusCell> {
+        self.virus_cells.iter().cloned()
+    }
+
+    fn player_infos(&self) -> impl Iterator<Item = &PlayerInfo> {
+        self.player_infos.iter()
+    }
+
+    fn view_area(&self) -> Circle {
+        self.view_area
+    }
+
+    fn world_bounds(&self) -> Rect {
+        self.world_bounds
+    }
+
+    fn owner(&self) -> PlayerId {
+        self.owner
+    }
+
+    fn leaderboard(&self) -> impl Iterator<Item = LeaderboardEntry> {
+        self.leaderboard.iter().copied()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        cells::cell::Cell,
+        color::HSL,
+        ids::{FoodCellId, Id, PlayerCellId},
+        pos::{Point, Vec2},
+        protocol::delta::PlayerCellUpdate,
+    };
+
+    use super::*;
+
+    fn input() -> PlayerInput {
+        PlayerInput {
+            move_vec: Vec2 { x: 0.25, y: -0.5 },
+            split: true,
+            eject: false,
+            freeze: true,
+        }
+    }
+
+    fn client_messages() -> Vec<ClientMessage> {
+        vec![
+            ClientMessage::Hello {
+                version: 7,
+                name: "Player".into(),
+            },
+            ClientMessage::Input {
+                seq: 1000,
+                input: input(),
+            },
+            ClientMessage::Ack(70_000),
+        ]
+    }
+
+    fn delta() -> ViewDelta {
+        let color = HSL::new(10, 20, 30);
+        ViewDelta {
+            frame: 12,
+            baseline: Some(11),
+            input_ack: 5,
+            owner: PlayerId::new(2),
+            view_area: Circle {
+                center: Point { x: 50.0, y: 60.0 },
+                radius: 300.0,
+            },
+            world_bounds: Rect {
+                top_left: Point { x: 0.0, y: 0.0 },
+                width: 1000.0,
+                height: 800.0,
+            },
+            player_cells: vec![
+                PlayerCellUpdate {
+                    id: PlayerCellId::new(1),
+                    owner: Some(PlayerId::new(2)),
+                    pos: Some(Point { x: 1.5, y: 2.5 }),
+                    mass: Some(25.25),
+                    velocity: Some(Vec2 { x: 3.0, y: -4.0 }),
+                },
+                PlayerCellUpdate {
+                    id: PlayerCellId::new(4),
+                    owner: None,
+                    pos: None,
+                    mass: Some(40.0),
+                    velocity: None,
+                },
+            ],
+            removed_player_cells: vec![PlayerCellId::new(3)],
+            food_cells: vec![FoodCell::from_parts(
+                FoodCellId::new(8),
+                Point { x: 7.0, y: 9.0 },
+                color,
+            )],
+            removed_food_cells: vec![FoodCellId::new(6)],
+            ejected_mass_cells: vec![EjectedMassCell::new(
+                Point { x: 11.0, y: 12.0 },
+                Vec2 { x: 13.0, y: 14.0 },
+                color,
+            )],
+            virus_cells: vec![VirusCell::from_parts(
+                Point { x: 15.0, y: 16.0 },
+                100.0,
+                Vec2::ZERO,
+            )],
+            player_infos: vec![PlayerInfo::from_parts(PlayerId::new(2), "Me".into(), color)],
+            removed_player_infos: vec![PlayerId::new(5)],
+            leaderboard: vec![LeaderboardEntry {
+                rank: 1,
+                player: PlayerId::new(2),
+                mass: 65.25,
+            }],
+        }
+    }
+
+    fn server_messages() -> Vec<ServerMessage> {
+        vec![
+            ServerMessage::Welcome {
+                version: 7,
+                player: PlayerId::new(300),
+            },
+            ServerMessage::VersionMismatch { version: 65535 },
+            ServerMessage::View(Box::new(delta())),
+        ]
+    }
+
+    #[test]
+    fn hello_round_trips() {
+        let frame = client_messages().remove(0).encode();
+        match ClientMessage::decode(&frame) {
+            Ok(ClientMessage::Hello { version, name }) => {
+                assert_eq!(version, 7);
+                assert_eq!(name, "Player");
+            }
+            _ => panic!("hello did not round trip"),
+        }
+    }
+
+    #[test]
+    fn input_round_trips() {
+        let frame = client_messages().remove(1).encode();
+        match ClientMessage::decode(&frame) {
+            Ok(ClientMessage::Input { seq, input }) => {
+                assert_eq!(seq, 1000);
+                assert_eq!((input.move_vec.x, input.move_vec.y), (0.25, -0.5));
+                assert_eq!(
+                    (input.split, input.eject, input.freeze),
+                    (true, false, true)
+                );
+            }
+            _ => panic!("input did not round trip"),
+        }
+    }
+
+    #[test]
+    fn ack_round_trips() {
+        let frame = client_messages().remove(2).encode();
+        assert!(matches!(
+            ClientMessage::decode(&frame),
+            Ok(ClientMessage::Ack(70_000))
+        ));
+    }
+
+    #[test]
+    fn welcome_round_trips() {
+        let frame = server_messages().remove(0).encode();
+        match ServerMessage::decode(&frame) {
+            Ok(ServerMessage::Welcome { version, player }) => {
+                assert_eq!(version, 7);
+                assert_eq!(player.value(), 300);
+            }
+            _ => panic!("welcome did not round trip"),
+        }
+    }
+
+    #[test]
+    fn version_mismatch_round_trips() {
+        let frame = server_messages().remove(1).encode();
+        assert!(matches!(
+            ServerMessage::decode(&frame),
+            Ok(ServerMessage::VersionMismatch { version: 65535 })
+        ));
+    }
+
+    #[test]
+    fn view_round_trips() {
+        let frame = server_messages().remove(2).encode();
+        let Ok(ServerMessage::View(decoded)) = ServerMessage::decode(&frame) else {
+            panic!("view did not round trip");
+        };
+        // Every value in `delta` is a multiple of its quantization step, so
+        // it comes back exactly
+        let expected = delta();
+        assert_eq!(
+            (decoded.frame, decoded.baseline, decoded.input_ack),
+            (12, Some(11), 5)
+        );
+        assert_eq!(decoded.owner.value(), 2);
+        assert!(decoded.view_area == expected.view_area);
+        assert!(decoded.world_bounds == expected.world_bounds);
+
+        assert_eq!(decoded.player_cells.len(), 2);
+        for (decoded, expected) in decoded.player_cells.iter().zip(&expected.player_cells) {
+            assert!(decoded.id == expected.id);
+            assert!(decoded.owner == expected.owner);
+            assert!(decoded.pos == expected.pos);
+            assert!(decoded.mass == expected.mass);
+            assert!(decoded.velocity == expected.velocity);
+        }
+        assert!(decoded.removed_player_cells == expected.removed_player_cells);
+
+        let food = &decoded.food_cells[..];
+        assert_eq!(food.len(), 1);
+        assert_eq!(food[0].id().value(), 8);
+        assert!(food[0].pos() == Point { x: 7.0, y: 9.0 });
+        assert!(decoded.removed_food_cells == expected.removed_food_cells);
+
+        let ejected = &decoded.ejected_mass_cells[..];
+        assert_eq!(ejected.len(), 1);
+        assert!(ejected[0].pos() == Point { x: 11.0, y: 12.0 });
+        assert!(ejected[0].velocity() == Vec2 { x: 13.0, y: 14.0 });
+
+        let viruses = &decoded.virus_cells[..];
+        assert_eq!(viruses.len(), 1);
+        assert!(viruses[0].pos() == Point { x: 15.0, y: 16.0 });
+        assert_eq!(viruses[0].mass(), 100.0);
+
+        let infos = &decoded.player_infos[..];
+        assert_eq!(infos.len(), 1);
+        assert_eq!((infos[0].id().value(), infos[0].name()), (2, "Me"));
+        assert!(decoded.removed_player_infos == expected.removed_player_infos);
+        assert!(decoded.leaderboard == expected.leaderboard);
+    }
+
+    #[test]
+    fn view_without_baseline_round_trips() {
+        let mut delta = delta();
+        delta.baseline = None;
+        let frame = ServerMessage::View(Box::new(delta)).encode();
+        let Ok(ServerMessage::View(decoded)) = ServerMessage::decode(&frame) else {
+            panic!("view did not round trip");
+        };
+        assert_eq!(decoded.baseline, None);
+    }
+
+    #[test]
+    fn every_prefix_of_a_frame_ends_unexpectedly() {
+        for message in client_messages() {
+            let frame = message.encode();
+            for len in 0..frame.len() {
+                assert_eq!(
+                    ClientMessage::decode(&frame[..len]).err(),
+                    Some(DecodeError::UnexpectedEnd),
+                    "{:?} of {:?}",
+                    &frame[..len],
+                    frame
+                );
+            }
+        }
+        for message in server_messages() {
+            let frame = message.encode();
+            for len in 0..frame.len() {
+                assert_eq!(
+                    ServerMessage::decode(&frame[..len]).err(),
+                    Some(DecodeError::UnexpectedEnd),
+                    "{:?} of {:?}",
+                    &frame[..len],
+                    frame
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn unknown_tags_are_rejected() {
+        assert_eq!(
+            ClientMessage::decode(&[3]).err(),
+            Some(DecodeError::UnknownMessage(3))
+        );
+        assert_eq!(
+            ServerMessage::decode(&[255, 0]).err(),
+            Some(DecodeError::UnknownMessage(255))
+        );
+    }
+
+    #[test]
+    fn trailing_bytes_are_rejected() {
+        let mut frame = ClientMessage::Ack(1).encode();
+        frame.extend([0, 0]);
+        assert_eq!(
+            ClientMessage::decode(&frame).err(),
+            Some(DecodeError::TrailingBytes(2))
+        );
+
+        let mut frame = server_messages().remove(2).encode();
+        frame.push(0);
+        assert_eq!(
+            ServerMessage::decode(&frame).err(),
+            Some(DecodeError::TrailingBytes(1))
+        );
+    }
+
+    #[test]
+    fn bad_input_flags_are_rejected() {
+        let mut frame = client_messages().remove(1).encode();
+        *frame.last_mut().unwrap() |= 0x80;
+        assert_eq!(
+            ClientMessage::decode(&frame).err(),
+            Some(DecodeError::OutOfRange("input flags"))
+        );
+    }
+}
@@ -0,0 +1,30 @@
+//! A compact binary encoding for the messages sent between game servers and
+//! clients. Positions and masses are quantized to fixed point and ids are
+//! sent as varints, so a typical view is a fraction of the size of its JSON.
+//!
+//! A client opens a connection by sending [`ClientMessage::Hello`] with the
+//! version of the protocol it speaks. The server answers with
+//! [`ServerMessage::Welcome`] if it speaks the same version, or
+//! [`ServerMessage::VersionMismatch`] before closing the connection if it
+//! doesn't.
+//!
+//! Views are sent as deltas against the last view the client acknowledged
+//! with [`ClientMessage::Ack`], so food that stays in view is only sent once.
+//! The leaderboard changes nearly every tick, so it is always sent in full.
+//!
+//! Inputs are numbered, and each view says which input it was simulated up
+//! to, so clients can predict their own movement ahead of the server.
+//!
+//! [`ClientMessage::Hello`]: messages::ClientMessage::Hello
+//! [`ClientMessage::Ack`]: messages::ClientMessage::Ack
+//! [`ServerMessage::Welcome`]: messages::ServerMessage::Welcome
+//! [`ServerMessage::VersionMismatch`]: messages::ServerMessage::VersionMismatch
+
+pub mod codec;
+pub mod decode_error;
+pub mod delta;
+pub mod messages;
+
+/// The version of the protocol spoken by this build. This must be bumped
+/// whenever the encoding of any message changes.
+pub const PROTOCOL_VERSION: u16 = 7;
@@ -0,0 +1,330 @@
+use std::{error::Error, fmt, fs, io, path::Path};
+
+use serde::{Deserialize, Serialize};
+
+use crate::pos::{Point, Rect};
+
+use super::game_server::GameServer;
+
+/// Tunable settings for a game server. Any setting missing from a config file
+/// keeps its default value.
+#[derive(Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct GameConfig {
+    /// How many times the game is ticked per second
+    pub tick_rate: usize,
+    /// The width of the game area
+    pub width: f64,
+    /// The height of the game area
+    pub height: f64,
+    /// How much food the server keeps on the map
+    pub food_amount: usize,
+    /// How many times larger the view area of a player is than their cells
+    pub view_radius_multiplier: f64,
+    /// The mass of a newly spawned player cell. Player cells never lose mass
+    /// below this.
+    pub spawn_mass: f64,
+    /// The fraction of mass player cells lose every second
+    pub mass_loss_per_second: f64,
+    /// The most cells that a single player can be split into at once
+    pub max_player_cells: usize,
+    /// How many viruses the server keeps on the map. Viruses are respawned
+    /// whenever there are fewer than this.
+    pub virus_count: usize,
+    /// The most viruses there can be on the map. Viruses that are fed enough
+    /// don't shoot out a new virus once there are this many.
+    pub max_viruses: usize,
+    /// How fast player cells can move depending on their mass
+    pub speed_curve: SpeedCurve,
+    /// How many of the top players are shown on the leaderboard
+    pub leaderboard_size: usize,
+}
+
+impl GameConfig {
+    /// The largest the width and height can be. The spatial indices split the
+    /// game area into tiles, and this keeps them to 512 tiles along each side.
+    pub const MAX_SIZE: f64 = GameServer::INDEX_TILE_SIZE * 512.0;
+
+    /// Loads a config from a TOML or JSON file, depending on the file
+    /// extension. Files without a `.json` extension are read as TOML.
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, ConfigError> {
+        let path = path.as_ref();
+        let contents = fs::read_to_string(path).map_err(ConfigError::Io)?;
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("json") => Self::from_json_str(&contents),
+            _ => Self::from_toml_str(&contents),
+        }
+    }
+
+    pub fn from_toml_str(toml: &str) -> Result<Self, ConfigError> {
+        let config: Self = toml::from_str(toml).map_err(|e| ConfigError::Parse(e.to_string()))?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    pub fn from_json_str(json: &str) -> Result<Self, ConfigError> {
+        let config: Self =
+            serde_json::from_str(json).map_err(|e| ConfigError::Parse(e.to_string()))?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Checks that every setting is within a range that the game can run with
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        check(self.tick_rate > 0, "tick_rate", "must be greater than zero")?;
+        check(self.width > 0.0, "width", "must be positive")?;
+        check(
+            self.width <= Self::MAX_SIZE,
+            "width",
+            "must be finite and at most 65536",
+        )?;
+        check(self.height > 0.0, "height", "must be positive")?;
+        check(
+            self.height <= Self::MAX_SIZE,
+            "height",
+            "must be finite and at most 65536",
+        )?;
+        check(
+            self.view_radius_multiplier > 0.0 && self.view_radius_multiplier.is_finite(),
+            "view_radius_multiplier",
+            "must be positive and finite",
+        )?;
+        check(
+            self.spawn_mass > 0.0 && self.spawn_mass.is_finite(),
+            "spawn_mass",
+            "must be positive and finite",
+        )?;
+        check(
+            (0.0..1.0).contains(&self.mass_loss_per_second),
+            "mass_loss_per_second",
+            "must be at least 0 and less than 1",
+        )?;
+        check(
+            self.max_player_cells > 0,
+            "max_player_cells",
+            "must be greater than zero",
+        )?;
+        check(
+            self.max_viruses >= self.virus_count,
+            "max_viruses",
+            "must be at least virus_count",
+        )?;
+        self.speed_curve.validate()
+    }
+
+    /// The area that the game takes place in
+    pub fn bounds(&self) -> Rect {
+        Rect {
+            top_left: Point { x: 0.0, y: 0.0 },
+            width: self.width,
+            height: self.height,
+        }
+    }
+
+    /// The fraction of mass player cells lose every tick
+    pub fn mass_loss_per_tick(&self) -> f64 {
+        self.mass_loss_per_second / self.tick_rate as f64
+    }
+}
+
+impl Default for GameConfig {
+    fn default() -> Self {
+        Self {
+            tick_rate: 60,
+            width: 1920.0,
+            height: 1080.0,
+            food_amount: 100,
+            view_radius_multiplier: 30.0,
+            spawn_mass: 20.0,
+            mass_loss_per_second: 0.01,
+            max_player_cells: 16,
+            virus_count: 10,
+            max_viruses: 20,
+            speed_curve: SpeedCurve::default(),
+            leaderboard_size: 10,
+        }
+    }
+}
+
+/// Maps the mass of a player cell to the furthest it can move in a tick.
+/// Speed falls off as a power of mass, so larger cells are slower:
+/// `base_speed * (base_mass / mass) ^ exponent`, clamped to be between
+/// `min_speed` and `base_speed`.
+#[derive(Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct SpeedCurve {
+    /// The speed of a cell with a mass of `base_mass` or less
+    pub base_speed: f64,
+    pub base_mass: f64,
+    /// How quickly speed falls off as mass increases
+    pub exponent: f64,
+    /// The speed of even the largest cells
+    pub min_speed: f64,
+}
+
+impl SpeedCurve {
+    pub fn max_speed(&self, mass: f64) -> f64 {
+        if mass <= self.base_mass {
+            return self.base_speed;
+        }
+        (self.base_speed * (self.base_mass / mass).powf(self.exponent)).max(self.min_speed)
+    }
+
+    fn validate(&self) -> Result<(), ConfigError> {
+        check(
+            self.base_speed > 0.0 && self.base_speed.is_finite(),
+            "speed_curve.base_speed",
+            "must be positive and finite",
+        )?;
+        check(
+            self.base_mass > 0.0 && self.base_mass.is_finite(),
+            "speed_curve.base_mass",
+            "must be positive and finite",
+        )?;
+        check(
+            self.exponent >= 0.0 && self.exponent.is_finite(),
+            "speed_curve.exponent",
+            "must not be negative and must be finite",
+        )?;
+        check(
+            self.min_speed > 0.0 && self.min_speed <= self.base_speed,
+            "speed_curve.min_speed",
+            "must be positive and at most base_speed",
+        )
+    }
+}
+
+impl Default for SpeedCurve {
+    fn default() -> Self {
+        Self {
+            base_speed: 10.0,
+            base_mass: 20.0,
+            exponent: 0.4,
+            min_speed: 2.0,
+        }
+    }
+}
+
+fn check(valid: bool, field: &'static str, reason: &'static str) -> Result<(), ConfigError> {
+    if valid {
+        Ok(())
+    } else {
+        Err(ConfigError::Invalid { field, reason })
+    }
+}
+
+#[derive(Debug)]
+pub enum ConfigError {
+    /// The config file couldn't be read
+    Io(io::Error),
+    /// The config couldn't be parsed as TOML or JSON
+    Parse(String),
+    /// A setting has a value that the game can't run with
+    Invalid {
+        field: &'static str,
+        reason: &'static str,
+    },
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "could not read config: {}", e),
+            Self::Parse(e) => write!(f, "could not parse config: {}", e),
+            Self::Invalid { field, reason } => write!(f, "invalid config: {} {}", field, reason),
+        }
+    }
+}
+
+impl Error for ConfigError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn invalid_field(config: &GameConfig) -> Option<&'static str> {
+        match config.validate() {
+            Ok(()) => None,
+            Err(ConfigError::Invalid { field, .. }) => Some(field),
+            Err(e) => panic!("unexpected error: {}", e),
+        }
+    }
+
+    #[test]
+    fn default_config_is_valid() {
+        assert_eq!(invalid_field(&GameConfig::default()), None);
+    }
+
+    #[test]
+    fn sizes_must_be_positive_and_finite() {
+        for size in [0.0, -1.0, f64::NAN, f64::INFINITY, f64::NEG_INFINITY] {
+            let config = GameConfig {
+                width: size,
+                ..GameConfig::default()
+            };
+            assert_eq!(invalid_field(&config), Some("width"), "width {}", size);
+            let config = GameConfig {
+                height: size,
+                ..GameConfig::default()
+            };
+            assert_eq!(invalid_field(&config), Some("height"), "height {}", size);
+        }
+    }
+
+    #[test]
+    fn sizes_are_capped() {
+        let config = GameConfig {
+            width: GameConfig::MAX_SIZE,
+            height: GameConfig::MAX_SIZE,
+            ..GameConfig::default()
+        };
+        assert_eq!(invalid_field(&config), None);
+
+        let config = GameConfig {
+            width: 1e300,
+            ..GameConfig::default()
+        };
+        assert_eq!(invalid_field(&config), Some("width"));
+        let config = GameConfig {
+            height: GameConfig::MAX_SIZE * 2.0,
+            ..GameConfig::default()
+        };
+        assert_eq!(invalid_field(&config), Some("height"));
+    }
+
+    #[test]
+    fn scales_must_be_finite() {
+        let config = GameConfig {
+            view_radius_multiplier: f64::INFINITY,
+            ..GameConfig::default()
+        };
+        assert_eq!(invalid_field(&config), Some("view_radius_multiplier"));
+        let config = GameConfig {
+            spawn_mass: f64::NAN,
+            ..GameConfig::default()
+        };
+        assert_eq!(invalid_field(&config), Some("spawn_mass"));
+        let config = GameConfig {
+            mass_loss_per_second: f64::NAN,
+            ..GameConfig::default()
+        };
+        assert_eq!(invalid_field(&config), Some("mass_loss_per_second"));
+        let config = GameConfig {
+            speed_curve: SpeedCurve {
+                base_mass: f64::INFINITY,
+                ..SpeedCurve::default()
+            },
+            ..GameConfig::default()
+        };
+        assert_eq!(invalid_field(&config), Some("speed_curve.base_mass"));
+    }
+
+    #[test]
+    fn huge_sizes_are_rejected_when_parsed() {
+        let err = GameConfig::from_toml_str("width = 1e300").err();
+        assert!(matches!(
+            err,
+            Some(ConfigError::Invalid { field: "width", .. })
+        ));
+    }
+}
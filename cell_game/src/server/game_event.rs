@@ -0,0 +1,12 @@
+use crate::ids::PlayerId;
+
+/// Something notable that happened during a tick of the game
+#[derive(Clone, Copy)]
+pub enum GameEvent {
+    /// A player lost their last cell
+    PlayerDied {
+        player: PlayerId,
+        /// The player who ate the last cell, if any
+        killer: Option<PlayerId>,
+    },
+}
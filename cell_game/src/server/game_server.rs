@@ -1,141 +1,838 @@
-use std::iter::repeat_with;
+use std::{
+    collections::{HashMap, HashSet},
+    iter::repeat_with,
+};
+
+use rand::{rngs::StdRng, thread_rng, Rng, SeedableRng};
 
 use crate::{
-    cells::{cell::Cell, food_cell::FoodCell, player_cell::PlayerCell},
+    bot::{bot_connection::BotConnection, difficulty::Difficulty},
+    cells::{
+        cell::Cell, ejected_mass_cell::EjectedMassCell, food_cell::FoodCell,
+        player_cell::PlayerCell, virus_cell::VirusCell,
+    },
     client_connection::ClientConnection,
-    ids::{IdGenerator, PlayerCellId, PlayerId},
+    ids::{FoodCellId, IdGenerator, PlayerCellId, PlayerId},
+    leaderboard::Leaderboard,
     player_info::PlayerInfo,
     pos::{Circle, Point, Rect},
+    spatial_index::{IndexedCells, SpatialIndex},
 };
 
-use super::{player_connection::PlayerConnection, server_view::ServerView};
+use super::{
+    game_config::GameConfig,
+    game_event::GameEvent,
+    player_connection::PlayerConnection,
+    player_stats::PlayerStats,
+    replay::{Replay, ReplayStep},
+    server_view::ServerView,
+    spawn_strategy::{SafeSpawn, SpawnStrategy},
+};
 
 pub struct GameServer {
     players: Vec<PlayerCell>,
     food: Vec<FoodCell>,
+    ejected_mass: Vec<EjectedMassCell>,
+    viruses: Vec<VirusCell>,
     bounds: Rect,
+    config: GameConfig,
+
+    player_index: SpatialIndex,
+    food_index: SpatialIndex,
+    ejected_mass_index: SpatialIndex,
+    virus_index: SpatialIndex,
 
     player_id_gen: IdGenerator<PlayerId>,
     player_cell_id_gen: IdGenerator<PlayerCellId>,
+    food_cell_id_gen: IdGenerator<FoodCellId>,
 
     player_infos: Vec<PlayerInfo>,
+    leaderboard: Leaderboard,
 
     connections: Vec<PlayerConnection>,
+
+    spawn_strategy: Box<dyn SpawnStrategy>,
+
+    events: Vec<GameEvent>,
+
+    /// All randomness in the game comes from this, so that the same seed and
+    /// the same inputs always play out the same way
+    rng: StdRng,
+    seed: u64,
+
+    /// Only set if the server was created with `GameServer::with_recording`
+    recording: Option<Replay>,
+}
+
+impl Default for GameServer {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl GameServer {
-    pub const TICK_RATE: usize = 60;
-    const GAME_BOUNDS: Rect = Rect {
-        top_left: Point { x: 0.0, y: 0.0 },
-        width: 1920.0,
-        height: 1080.0,
-    };
-    const VIEW_RADIUS_MULTIPLIER: f64 = 30.0;
-    const FOOD_AMOUNT: usize = 100;
+    /// The width and height of each tile in the spatial indices
+    pub(crate) const INDEX_TILE_SIZE: f64 = 128.0;
 
     pub fn new() -> Self {
-        let bounds = Self::GAME_BOUNDS;
-        let food = Self::n_food(bounds, Self::FOOD_AMOUNT).collect();
+        Self::with_config(GameConfig::default())
+    }
+
+    /// Creates a server with custom settings. The config should already have
+    /// been checked with `GameConfig::validate`.
+    pub fn with_config(config: GameConfig) -> Self {
+        Self::with_seed(config, thread_rng().gen())
+    }
+
+    /// Creates a server whose randomness is seeded with `seed`. Servers with
+    /// the same config and seed that are given the same inputs produce
+    /// identical games.
+    pub fn with_seed(config: GameConfig, seed: u64) -> Self {
+        let bounds = config.bounds();
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut food_cell_id_gen = IdGenerator::new();
+        let food =
+            Self::n_food(bounds, config.food_amount, &mut food_cell_id_gen, &mut rng).collect();
+        let viruses = Self::n_viruses(bounds, config.virus_count, &mut rng).collect();
+        let leaderboard = Leaderboard::new(config.leaderboard_size);
         Self {
             players: Vec::new(),
             food,
+            ejected_mass: Vec::new(),
+            viruses,
             bounds,
+            config,
+            player_index: SpatialIndex::new(bounds, Self::INDEX_TILE_SIZE),
+            food_index: SpatialIndex::new(bounds, Self::INDEX_TILE_SIZE),
+            ejected_mass_index: SpatialIndex::new(bounds, Self::INDEX_TILE_SIZE),
+            virus_index: SpatialIndex::new(bounds, Self::INDEX_TILE_SIZE),
             player_id_gen: IdGenerator::new(),
             player_cell_id_gen: IdGenerator::new(),
+            food_cell_id_gen,
             player_infos: Vec::new(),
+            leaderboard,
             connections: Vec::new(),
+            spawn_strategy: Box::new(SafeSpawn::default()),
+            events: Vec::new(),
+            rng,
+            seed,
+            recording: None,
         }
     }
 
+    /// Creates a seeded server that records everything done to it, so that
+    /// the game can be played back later with a `ReplayPlayer`
+    pub fn with_recording(config: GameConfig, seed: u64) -> Self {
+        let recording = Replay::new(seed, config.clone());
+        Self {
+            recording: Some(recording),
+            ..Self::with_seed(config, seed)
+        }
+    }
+
+    pub fn config(&self) -> &GameConfig {
+        &self.config
+    }
+
+    /// The seed the server's randomness was created from
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Everything recorded so far, if the server is recording
+    pub fn replay(&self) -> Option<&Replay> {
+        self.recording.as_ref()
+    }
+
+    pub fn player_infos(&self) -> &[PlayerInfo] {
+        &self.player_infos
+    }
+
+    /// Changes where new player cells are spawned
+    pub fn set_spawn_strategy(&mut self, spawn_strategy: impl SpawnStrategy + 'static) {
+        self.spawn_strategy = Box::new(spawn_strategy);
+    }
+
     pub fn tick(&mut self) {
+        if let Some(recording) = &mut self.recording {
+            recording.record(ReplayStep::Tick(Vec::new()));
+        }
         self.handle_connections();
+        self.apply_velocities();
+        self.resolve_own_cells();
         self.feed_food();
+        self.feed_viruses();
+        self.feed_ejected_mass();
+        self.pop_players();
+        self.eat_players();
+        self.replenish_viruses();
         self.remove_mass();
+        self.leaderboard.update(&self.players);
+    }
+
+    /// Takes the events that have happened since the last time this was
+    /// called
+    pub fn drain_events(&mut self) -> impl Iterator<Item = GameEvent> + '_ {
+        self.events.drain(..)
+    }
+
+    /// Takes the events that have happened since the last time events were
+    /// drained, and respawns every player who died, for games where players
+    /// come back as soon as they die
+    pub fn respawn_dead_players(&mut self) {
+        let died: Vec<PlayerId> = self
+            .drain_events()
+            .map(|event| match event {
+                GameEvent::PlayerDied { player, .. } => player,
+            })
+            .collect();
+        for player in died {
+            self.respawn_player(player);
+        }
     }
 
     pub fn connect_player(
         &mut self,
         name: String,
         conn: Box<dyn for<'a> ClientConnection<'a, V = ServerView<'a>>>,
-    ) {
-        let player_info = PlayerInfo::new(name, &mut self.player_id_gen);
+    ) -> PlayerId {
+        self.record(|| ReplayStep::Connect {
+            name: name.clone(),
+            bot: false,
+        });
+        self.add_player(name, conn)
+    }
 
-        self.connections
-            .push(PlayerConnection::new(conn, player_info.id()));
+    fn add_player(
+        &mut self,
+        name: String,
+        conn: Box<dyn for<'a> ClientConnection<'a, V = ServerView<'a>>>,
+    ) -> PlayerId {
+        let player_info = PlayerInfo::new(name, &mut self.player_id_gen, &mut self.rng);
+        let id = player_info.id();
 
-        self.players.push(PlayerCell::new(
-            self.bounds.center(),
-            player_info.id(),
-            &mut self.player_cell_id_gen,
-        ));
+        let view_area = self.spawn_player_cell(id);
+        self.connections
+            .push(PlayerConnection::new(conn, id, view_area));
 
         self.player_infos.push(player_info);
+        id
     }
 
+    /// Adds a computer controlled player to the game. The bot is seeded from
+    /// the server's randomness, so seeded games with bots are still
+    /// reproducible.
+    pub fn connect_bot(&mut self, name: String, difficulty: Difficulty) -> PlayerId {
+        self.record(|| ReplayStep::Connect {
+            name: name.clone(),
+            bot: true,
+        });
+        let bot = BotConnection::new(difficulty, self.rng.gen());
+        self.add_player(name, Box::new(bot))
+    }
+
+    /// Connects a player that is being played back from a replay. Bots use up
+    /// the same randomness here that they did when they were recorded.
+    pub(crate) fn connect_replayed(
+        &mut self,
+        name: String,
+        bot: bool,
+        conn: Box<dyn for<'a> ClientConnection<'a, V = ServerView<'a>>>,
+    ) -> PlayerId {
+        if bot {
+            self.rng.gen::<u64>();
+        }
+        self.add_player(name, conn)
+    }
+
+    /// What a player can currently see, the same as the view their connection
+    /// will be given next tick. Returns `None` if the player isn't connected.
+    pub fn player_view(&mut self, player: PlayerId) -> Option<ServerView<'_>> {
+        let conn = self.connections.iter().find(|conn| conn.id() == player)?;
+        let view_area = Self::player_view_area(
+            self.players.iter().filter(|cell| cell.owner() == player),
+            self.config.view_radius_multiplier,
+        )
+        .unwrap_or(conn.view_area());
+        self.rebuild_indices();
+        Some(ServerView::new(
+            IndexedCells::new(&self.players, &self.player_index),
+            IndexedCells::new(&self.food, &self.food_index),
+            IndexedCells::new(&self.ejected_mass, &self.ejected_mass_index),
+            IndexedCells::new(&self.viruses, &self.virus_index),
+            &self.player_infos,
+            &self.leaderboard,
+            view_area,
+            self.bounds,
+            player,
+        ))
+    }
+
+    /// The total mass of all of a player's cells
+    pub fn player_mass(&self, player: PlayerId) -> f64 {
+        self.players
+            .iter()
+            .filter(|cell| cell.owner() == player)
+            .map(|cell| cell.mass())
+            .sum()
+    }
+
+    /// What a player has done since they connected. Returns `None` if the
+    /// player isn't connected.
+    pub fn player_stats(&self, player: PlayerId) -> Option<PlayerStats> {
+        self.connections
+            .iter()
+            .find(|conn| conn.id() == player)
+            .map(|conn| conn.stats())
+    }
+
+    /// Removes a player's connection, info and cells from the game. Returns
+    /// false if the player wasn't connected.
+    pub fn disconnect_player(&mut self, player: PlayerId) -> bool {
+        let connected = self.connections.iter().any(|conn| conn.id() == player);
+        if !connected {
+            return false;
+        }
+        self.record(|| ReplayStep::Disconnect(player));
+        self.connections.retain(|conn| conn.id() != player);
+        self.player_infos.retain(|info| info.id() != player);
+        self.players.retain(|cell| cell.owner() != player);
+        true
+    }
+
+    /// Gives a dead player a new cell. Returns false if the player isn't
+    /// connected or is still alive.
+    pub fn respawn_player(&mut self, player: PlayerId) -> bool {
+        let dead = self
+            .connections
+            .iter()
+            .any(|conn| conn.id() == player && !conn.alive());
+        if !dead {
+            return false;
+        }
+        self.record(|| ReplayStep::Respawn(player));
+        let view_area = self.spawn_player_cell(player);
+        if let Some(conn) = self.connections.iter_mut().find(|conn| conn.id() == player) {
+            conn.revive(view_area);
+        }
+        true
+    }
+
+    fn record(&mut self, step: impl FnOnce() -> ReplayStep) {
+        if let Some(recording) = &mut self.recording {
+            recording.record(step());
+        }
+    }
+
+    /// Adds a new cell for a player, returning the view area around it
+    fn spawn_player_cell(&mut self, owner: PlayerId) -> Circle {
+        let pos = self.spawn_strategy.spawn_point(
+            owner,
+            self.config.spawn_mass,
+            self.bounds,
+            &self.players,
+            &mut self.rng,
+        );
+        let cell = PlayerCell::new(pos, owner, &self.config, &mut self.player_cell_id_gen);
+        let view_area = cell
+            .hitbox()
+            .scale_centered(self.config.view_radius_multiplier);
+        self.players.push(cell);
+        view_area
+    }
+
+    /// Food never moves, so the food index built at the start of the tick is
+    /// still up to date here.
     fn feed_food(&mut self) {
-        let mut eaten = 0;
+        let mut eaten = vec![false; self.food.len()];
+        let mut eaten_count = 0;
+        let mut eaten_by = HashMap::new();
         for player_cell in self.players.iter_mut() {
             let hitbox = player_cell.hitbox();
-            self.food.retain(|food_cell| {
-                if hitbox.contains_point(food_cell.pos()) {
+            for i in self.food_index.near(hitbox) {
+                let food_cell = &self.food[i];
+                if !eaten[i] && hitbox.contains_point(food_cell.pos()) {
                     player_cell.add_mass(food_cell.mass());
-                    eaten += 1;
+                    eaten[i] = true;
+                    eaten_count += 1;
+                    *eaten_by.entry(player_cell.owner()).or_insert(0) += 1;
+                }
+            }
+        }
+        for conn in self.connections.iter_mut() {
+            conn.stats_mut().food_eaten += eaten_by.get(&conn.id()).copied().unwrap_or(0);
+        }
+        let mut eaten = eaten.into_iter();
+        self.food.retain(|_| !eaten.next().unwrap_or(false));
+        self.food.extend(Self::n_food(
+            self.bounds,
+            eaten_count,
+            &mut self.food_cell_id_gen,
+            &mut self.rng,
+        ))
+    }
+
+    /// Lets viruses absorb ejected mass, shooting out new viruses once they
+    /// have been fed enough. Viruses fed while the map is full are reset
+    /// without shooting.
+    fn feed_viruses(&mut self) {
+        let viruses = self.viruses.len();
+        let max_viruses = self.config.max_viruses;
+        let mut new_viruses = Vec::new();
+        for virus in self.viruses.iter_mut() {
+            let hitbox = virus.hitbox();
+            self.ejected_mass.retain(|ejected| {
+                if hitbox.contains_point(ejected.pos()) {
+                    let shot = virus.feed(ejected.mass(), ejected.velocity());
+                    if viruses + new_viruses.len() < max_viruses {
+                        new_viruses.extend(shot);
+                    }
+                    false
+                } else {
+                    true
+                }
+            })
+        }
+        self.viruses.extend(new_viruses);
+    }
+
+    /// Pops player cells that are large enough to eat a virus into many
+    /// pieces, consuming the virus
+    fn pop_players(&mut self) {
+        let mut new_cells = Vec::new();
+        for i in 0..self.players.len() {
+            let cell = &mut self.players[i];
+            let Some(virus_idx) = self.viruses.iter().position(|v| cell.can_pop_on(v)) else {
+                continue;
+            };
+            let virus = self.viruses.swap_remove(virus_idx);
+            cell.add_mass(virus.mass());
+
+            let owner = cell.owner();
+            let cell_count = self
+                .players
+                .iter()
+                .chain(new_cells.iter())
+                .filter(|cell| cell.owner() == owner)
+                .count();
+            let max_pieces = self.config.max_player_cells.saturating_sub(cell_count);
+            new_cells.extend(self.players[i].pop(
+                max_pieces,
+                &self.config,
+                &mut self.player_cell_id_gen,
+            ));
+        }
+        self.players.extend(new_cells);
+    }
+
+    fn replenish_viruses(&mut self) {
+        let missing = self.config.virus_count.saturating_sub(self.viruses.len());
+        self.viruses
+            .extend(Self::n_viruses(self.bounds, missing, &mut self.rng));
+    }
+
+    fn feed_ejected_mass(&mut self) {
+        for player_cell in self.players.iter_mut() {
+            let hitbox = player_cell.hitbox();
+            self.ejected_mass.retain(|ejected| {
+                if hitbox.contains_point(ejected.pos()) {
+                    player_cell.add_mass(ejected.mass());
                     false
                 } else {
                     true
                 }
             })
         }
-        self.food.extend(Self::n_food(self.bounds, eaten))
+    }
+
+    /// Lets player cells eat the cells of other players that are small enough,
+    /// killing any player who loses their last cell.
+    fn eat_players(&mut self) {
+        self.player_index.rebuild(&self.players);
+        let mut eaten = HashSet::new();
+        let mut killers = Vec::new();
+        for i in 0..self.players.len() {
+            let hitbox = self.players[i].hitbox();
+            for j in self.player_index.near(hitbox) {
+                if i == j
+                    || eaten.contains(&self.players[i].id())
+                    || eaten.contains(&self.players[j].id())
+                    || !self.players[i].can_eat(&self.players[j])
+                {
+                    continue;
+                }
+                let prey = &self.players[j];
+                eaten.insert(prey.id());
+                killers.push((prey.owner(), self.players[i].owner()));
+                let prey_mass = prey.mass();
+                self.players[i].add_mass(prey_mass);
+            }
+        }
+        if eaten.is_empty() {
+            return;
+        }
+        self.players.retain(|cell| !eaten.contains(&cell.id()));
+
+        for &(_, killer) in killers.iter() {
+            if let Some(conn) = self.connections.iter_mut().find(|conn| conn.id() == killer) {
+                conn.stats_mut().cells_eaten += 1;
+            }
+        }
+        for (player, killer) in killers {
+            let alive = self.players.iter().any(|cell| cell.owner() == player);
+            let conn = self.connections.iter_mut().find(|conn| conn.id() == player);
+            if let Some(conn) = conn.filter(|conn| !alive && conn.alive()) {
+                conn.kill(Some(killer));
+                self.events.push(GameEvent::PlayerDied {
+                    player,
+                    killer: Some(killer),
+                });
+            }
+        }
     }
 
     fn remove_mass(&mut self) {
         for player_cell in self.players.iter_mut() {
-            player_cell.lose_mass();
+            player_cell.lose_mass(&self.config);
         }
     }
 
+    fn rebuild_indices(&mut self) {
+        self.player_index.rebuild(&self.players);
+        self.food_index.rebuild(&self.food);
+        self.ejected_mass_index.rebuild(&self.ejected_mass);
+        self.virus_index.rebuild(&self.viruses);
+    }
+
+    /// Asks every connection for its input, then applies the inputs. Every
+    /// connection is shown the game as it was at the start of the tick, so
+    /// the order connections are asked in doesn't matter.
     fn handle_connections(&mut self) {
+        self.rebuild_indices();
+        let owned = self.cells_by_owner();
+        let no_cells = Vec::new();
+
+        let mut inputs = Vec::with_capacity(self.connections.len());
         for conn in self.connections.iter_mut() {
             let owner = conn.id();
-            if let Some(view_area) = Self::player_view_area(&self.players, owner) {
-                let input = conn.connection().on_tick(ServerView::new(
-                    &self.players,
-                    &self.food,
-                    &self.player_infos,
-                    view_area,
-                    owner,
-                ));
-
-                let move_to = view_area.center.offset(input.move_vec);
-
-                Self::move_players(
-                    self.players.iter_mut().filter(|cell| cell.owner() == owner),
+            let cells = owned.get(&owner).unwrap_or(&no_cells);
+            if let Some(view_area) = Self::player_view_area(
+                cells.iter().map(|&i| &self.players[i]),
+                self.config.view_radius_multiplier,
+            ) {
+                conn.set_view_area(view_area);
+            }
+            let view_area = conn.view_area();
+
+            let input = conn.connection().on_tick(ServerView::new(
+                IndexedCells::new(&self.players, &self.player_index),
+                IndexedCells::new(&self.food, &self.food_index),
+                IndexedCells::new(&self.ejected_mass, &self.ejected_mass_index),
+                IndexedCells::new(&self.viruses, &self.virus_index),
+                &self.player_infos,
+                &self.leaderboard,
+                view_area,
+                self.bounds,
+                owner,
+            ));
+            if let Some(recording) = &mut self.recording {
+                recording.record_input(owner, &input);
+            }
+            if conn.alive() {
+                inputs.push((owner, view_area.center.offset(input.move_vec), input));
+            }
+        }
+
+        for (owner, move_to, input) in inputs {
+            let cells = owned.get(&owner).unwrap_or(&no_cells);
+            if !input.freeze {
+                for &i in cells {
+                    self.players[i].move_player(move_to, self.bounds, &self.config.speed_curve);
+                }
+            }
+
+            if input.split {
+                Self::split_player(
+                    &mut self.players,
+                    cells,
                     move_to,
-                    self.bounds,
+                    &self.config,
+                    &mut self.player_cell_id_gen,
                 );
             }
+
+            if input.eject {
+                let color = self
+                    .player_infos
+                    .iter()
+                    .find(|info| info.id() == owner)
+                    .map(|info| info.color());
+                if let Some(color) = color {
+                    for &i in cells {
+                        self.ejected_mass.extend(self.players[i].eject(
+                            move_to,
+                            color,
+                            &self.config,
+                        ));
+                    }
+                }
+            }
         }
     }
 
-    fn move_players<'a, T: Iterator<Item = &'a mut PlayerCell>>(
-        players: T,
-        move_to: Point,
-        bounds: Rect,
+    /// The indices of each player's cells in `players`
+    fn cells_by_owner(&self) -> HashMap<PlayerId, Vec<usize>> {
+        let mut owned: HashMap<PlayerId, Vec<usize>> = HashMap::new();
+        for (i, cell) in self.players.iter().enumerate() {
+            owned.entry(cell.owner()).or_default().push(i);
+        }
+        owned
+    }
+
+    /// Splits each of a player's cells that are large enough, largest first,
+    /// until the player reaches the maximum number of cells. `cells` are the
+    /// indices of all of the player's cells.
+    fn split_player(
+        players: &mut Vec<PlayerCell>,
+        cells: &[usize],
+        towards: Point,
+        config: &GameConfig,
+        id_gen: &mut IdGenerator<PlayerCellId>,
     ) {
-        for cell in players {
-            cell.move_player(move_to, bounds);
+        let mut cell_count = cells.len();
+        let mut splittable: Vec<usize> = cells
+            .iter()
+            .copied()
+            .filter(|&i| players[i].can_split(config))
+            .collect();
+        splittable.sort_by(|&a, &b| players[b].mass().total_cmp(&players[a].mass()));
+
+        let mut new_cells = Vec::new();
+        for i in splittable {
+            if cell_count >= config.max_player_cells {
+                break;
+            }
+            if let Some(new_cell) = players[i].split(towards, config, id_gen) {
+                new_cells.push(new_cell);
+                cell_count += 1;
+            }
         }
+        players.extend(new_cells);
     }
 
-    fn n_food(bounds: Rect, n: usize) -> impl Iterator<Item = FoodCell> {
-        repeat_with(move || FoodCell::new_within(bounds)).take(n)
+    fn apply_velocities(&mut self) {
+        for cell in self.players.iter_mut() {
+            cell.apply_velocity(self.bounds);
+        }
+        for cell in self.ejected_mass.iter_mut() {
+            cell.apply_velocity(self.bounds);
+        }
+        for cell in self.viruses.iter_mut() {
+            cell.apply_velocity(self.bounds);
+        }
     }
 
-    fn player_view_area(players: &Vec<PlayerCell>, owner: PlayerId) -> Option<Circle> {
-        players
-            .iter()
-            .find(|cell| cell.owner() == owner)
-            .map(|p| p.hitbox().scale_centered(Self::VIEW_RADIUS_MULTIPLIER))
+    /// Merges overlapping cells of the same owner once both of their merge
+    /// cooldowns have run out, and pushes them apart otherwise.
+    fn resolve_own_cells(&mut self) {
+        for cell in self.players.iter_mut() {
+            cell.tick_merge_cooldown();
+        }
+
+        self.player_index.rebuild(&self.players);
+        let mut merged = HashSet::new();
+        for i in 0..self.players.len() {
+            let mut candidates: Vec<usize> = self
+                .player_index
+                .near(self.players[i].hitbox())
+                .filter(|&j| j > i)
+                .collect();
+            candidates.sort_unstable();
+            for j in candidates {
+                let (left, right) = self.players.split_at_mut(j);
+                let (a, b) = (&mut left[i], &mut right[0]);
+                if a.owner() != b.owner()
+                    || merged.contains(&a.id())
+                    || merged.contains(&b.id())
+                    || !a.hitbox().overlaps_circle(b.hitbox())
+                {
+                    continue;
+                }
+
+                if a.can_merge() && b.can_merge() {
+                    let (larger, smaller) = if a.mass() >= b.mass() { (a, b) } else { (b, a) };
+                    larger.add_mass(smaller.mass());
+                    merged.insert(smaller.id());
+                } else {
+                    a.push_apart(b, self.bounds);
+                }
+            }
+        }
+        self.players.retain(|cell| !merged.contains(&cell.id()));
+    }
+
+    fn n_food<'a>(
+        bounds: Rect,
+        n: usize,
+        id_gen: &'a mut IdGenerator<FoodCellId>,
+        rng: &'a mut StdRng,
+    ) -> impl Iterator<Item = FoodCell> + 'a {
+        repeat_with(move || FoodCell::new_within(bounds, id_gen, rng)).take(n)
+    }
+
+    fn n_viruses(bounds: Rect, n: usize, rng: &mut StdRng) -> impl Iterator<Item = VirusCell> + '_ {
+        repeat_with(move || VirusCell::new_within(bounds, rng)).take(n)
+    }
+
+    /// The view area is centered on the center of mass of all of a player's
+    /// cells, and grows with their total mass.
+    fn player_view_area<'a>(
+        cells: impl Iterator<Item = &'a PlayerCell>,
+        view_radius_multiplier: f64,
+    ) -> Option<Circle> {
+        let (total_mass, weighted_x, weighted_y) =
+            cells.fold((0.0, 0.0, 0.0), |(mass, x, y), cell| {
+                let pos = cell.pos();
+                (
+                    mass + cell.mass(),
+                    x + pos.x * cell.mass(),
+                    y + pos.y * cell.mass(),
+                )
+            });
+        (total_mass > 0.0).then(|| {
+            Circle {
+                center: Point {
+                    x: weighted_x / total_mass,
+                    y: weighted_y / total_mass,
+                },
+                radius: (total_mass * PlayerCell::MASS_AREA_MULTIPLIER).sqrt(),
+            }
+            .scale_centered(view_radius_multiplier)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{client_connection::PlayerInput, ids::Id, pos::Vec2, test_support::small_config};
+
+    use super::*;
+
+    /// A player that circles around, splitting and ejecting on a fixed
+    /// schedule
+    struct ScriptedConnection {
+        tick: u32,
+        offset: f64,
+    }
+
+    impl<'a> ClientConnection<'a> for ScriptedConnection {
+        type V = ServerView<'a>;
+
+        fn on_tick(&mut self, _view: Self::V) -> PlayerInput {
+            self.tick += 1;
+            let angle = self.offset + self.tick as f64 / 20.0;
+            PlayerInput {
+                move_vec: Vec2 {
+                    x: angle.cos(),
+                    y: angle.sin(),
+                },
+                split: self.tick.is_multiple_of(45),
+                eject: self.tick.is_multiple_of(10),
+                ..Default::default()
+            }
+        }
+    }
+
+    /// Plays a scripted game, returning every cell after each tick
+    fn play(seed: u64) -> Vec<Vec<u64>> {
+        let mut game = GameServer::with_seed(small_config(), seed);
+        for i in 0..3 {
+            game.connect_player(
+                format!("Player {}", i),
+                Box::new(ScriptedConnection {
+                    tick: 0,
+                    offset: i as f64 * 2.0,
+                }),
+            );
+        }
+        // Players start out too small to eject, so they're fed first
+        for cell in game.players.iter_mut() {
+            cell.add_mass(400.0);
+        }
+        game.connect_bot("Bot".into(), Difficulty::Hard);
+
+        let mut split = false;
+        let mut ejected = false;
+        let snapshots = (0..300)
+            .map(|_| {
+                game.tick();
+                game.respawn_dead_players();
+                split |= game.players.len() > game.player_infos.len();
+                ejected |= !game.ejected_mass.is_empty();
+                game.cell_bits()
+            })
+            .collect();
+        assert!(split, "no player split");
+        assert!(ejected, "no player ejected mass");
+        snapshots
+    }
+
+    impl GameServer {
+        /// Every cell's position, mass and ids, with floats as their exact
+        /// bits, so that two games can be checked for being identical
+        pub(crate) fn cell_bits(&self) -> Vec<u64> {
+            let mut values = Vec::new();
+            let mut push_cell = |pos: Point, mass: f64| {
+                values.extend([pos.x.to_bits(), pos.y.to_bits(), mass.to_bits()]);
+            };
+            for cell in &self.players {
+                push_cell(cell.pos(), cell.mass());
+            }
+            for cell in &self.food {
+                push_cell(cell.pos(), cell.mass());
+            }
+            for cell in &self.ejected_mass {
+                push_cell(cell.pos(), cell.mass());
+            }
+            for cell in &self.viruses {
+                push_cell(cell.pos(), cell.mass());
+            }
+            values.extend(
+                self.players
+                    .iter()
+                    .map(|cell| (cell.id().value() as u64) << 32 | cell.owner().value() as u64),
+            );
+            values.extend(self.food.iter().map(|cell| cell.id().value() as u64));
+            values
+        }
+    }
+
+    #[test]
+    fn games_with_the_same_seed_are_identical() {
+        let first = play(42);
+        let second = play(42);
+        for (tick, (first, second)) in first.iter().zip(&second).enumerate() {
+            assert!(first == second, "games diverged on tick {}", tick);
+        }
+    }
+
+    #[test]
+    fn games_with_different_seeds_diverge() {
+        assert!(play(42) != play(43));
+    }
+
+    #[test]
+    fn calls_that_change_nothing_are_not_recorded() {
+        let mut game = GameServer::with_recording(small_config(), 5);
+        let player = game.connect_bot("Bot".into(), Difficulty::Easy);
+        let steps = game.replay().unwrap().steps().len();
+
+        assert!(!game.respawn_player(player), "living players can't respawn");
+        assert!(!game.disconnect_player(PlayerId::new(player.value() + 1)));
+        assert_eq!(game.replay().unwrap().steps().len(), steps);
+
+        assert!(game.disconnect_player(player));
+        assert!(!game.disconnect_player(player));
+        assert_eq!(game.replay().unwrap().steps().len(), steps + 1);
     }
 }
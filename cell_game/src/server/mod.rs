@@ -1,3 +1,9 @@
+pub mod game_config;
+pub mod game_event;
 pub mod game_server;
 mod player_connection;
+pub mod player_stats;
+pub mod replay;
+pub mod replay_player;
 pub mod server_view;
+pub mod spawn_strategy;
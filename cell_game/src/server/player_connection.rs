@@ -1,25 +1,70 @@
-use crate::{client_connection::ClientConnection, ids::PlayerId};
+use crate::{client_connection::ClientConnection, ids::PlayerId, pos::Circle};
 
-use super::server_view::ServerView;
+use super::{player_stats::PlayerStats, server_view::ServerView};
 
 pub(crate) struct PlayerConnection {
     connection: Box<dyn for<'a> ClientConnection<'a, V = ServerView<'a>>>,
     id: PlayerId,
+    /// The most recent view area of the player. Dead players keep watching
+    /// the area they died in.
+    view_area: Circle,
+    alive: bool,
+    stats: PlayerStats,
 }
 
 impl PlayerConnection {
     pub(crate) fn new(
         connection: Box<dyn for<'a> ClientConnection<'a, V = ServerView<'a>>>,
         id: PlayerId,
+        view_area: Circle,
     ) -> Self {
-        Self { connection, id }
+        Self {
+            connection,
+            id,
+            view_area,
+            alive: true,
+            stats: PlayerStats::default(),
+        }
     }
 
     pub(crate) fn id(&self) -> PlayerId {
         self.id
     }
 
-    pub(crate) fn connection(&mut self) -> &mut dyn ClientConnection<V = ServerView> {
+    pub(crate) fn connection(&mut self) -> &mut dyn ClientConnection<'_, V = ServerView<'_>> {
         self.connection.as_mut()
     }
+
+    pub(crate) fn view_area(&self) -> Circle {
+        self.view_area
+    }
+
+    pub(crate) fn set_view_area(&mut self, view_area: Circle) {
+        self.view_area = view_area;
+    }
+
+    pub(crate) fn alive(&self) -> bool {
+        self.alive
+    }
+
+    pub(crate) fn revive(&mut self, view_area: Circle) {
+        self.alive = true;
+        self.view_area = view_area;
+    }
+
+    pub(crate) fn stats(&self) -> PlayerStats {
+        self.stats
+    }
+
+    pub(crate) fn stats_mut(&mut self) -> &mut PlayerStats {
+        &mut self.stats
+    }
+
+    pub(crate) fn kill(&mut self, killer: Option<PlayerId>) {
+        if self.alive {
+            self.alive = false;
+            self.stats.deaths += 1;
+            self.connection.on_death(killer);
+        }
+    }
 }
@@ -0,0 +1,8 @@
+/// Running totals of what a player has done since they connected
+#[derive(Clone, Copy, Default)]
+pub struct PlayerStats {
+    pub food_eaten: u32,
+    /// Cells of other players that this player's cells ate
+    pub cells_eaten: u32,
+    pub deaths: u32,
+}
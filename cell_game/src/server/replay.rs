@@ -0,0 +1,152 @@
+use std::{error::Error, fmt, fs, io, path::Path};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{client_connection::PlayerInput, ids::PlayerId};
+
+use super::game_config::GameConfig;
+
+/// Everything needed to play a seeded game again exactly as it happened: the
+/// seed, the config, and every input and change of players in order.
+///
+/// Replays assume the server's default spawn strategy. Games that change it
+/// with `GameServer::set_spawn_strategy` won't play back the same way.
+#[derive(Clone, Deserialize, Serialize)]
+pub struct Replay {
+    seed: u64,
+    config: GameConfig,
+    steps: Vec<ReplayStep>,
+}
+
+/// One thing that was done to the server while it was recorded
+#[derive(Clone, Deserialize, Serialize)]
+pub enum ReplayStep {
+    /// A player joined. Bots are recorded separately because connecting one
+    /// uses some of the server's randomness.
+    Connect {
+        name: String,
+        bot: bool,
+    },
+    Disconnect(PlayerId),
+    Respawn(PlayerId),
+    /// The game ticked, and these are the inputs each connection gave,
+    /// in the order the server asked for them
+    Tick(Vec<(PlayerId, PlayerInput)>),
+}
+
+impl Replay {
+    pub(crate) fn new(seed: u64, config: GameConfig) -> Self {
+        Self {
+            seed,
+            config,
+            steps: Vec::new(),
+        }
+    }
+
+    /// Reads a replay saved with `Replay::save`
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, ReplayError> {
+        let contents = fs::read_to_string(path).map_err(ReplayError::Io)?;
+        serde_json::from_str(&contents).map_err(|e| ReplayError::Parse(e.to_string()))
+    }
+
+    /// Writes the replay to a file as JSON. Inputs are saved at full
+    /// precision, since rounding them would change how the game plays out.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), ReplayError> {
+        let json = serde_json::to_string(self).map_err(|e| ReplayError::Parse(e.to_string()))?;
+        fs::write(path, json).map_err(ReplayError::Io)
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    pub fn config(&self) -> &GameConfig {
+        &self.config
+    }
+
+    pub fn steps(&self) -> &[ReplayStep] {
+        &self.steps
+    }
+
+    /// How many ticks were recorded
+    pub fn ticks(&self) -> usize {
+        self.steps
+            .iter()
+            .filter(|step| matches!(step, ReplayStep::Tick(_)))
+            .count()
+    }
+
+    pub(crate) fn record(&mut self, step: ReplayStep) {
+        self.steps.push(step);
+    }
+
+    /// Adds an input to the tick currently being recorded
+    pub(crate) fn record_input(&mut self, player: PlayerId, input: &PlayerInput) {
+        if let Some(ReplayStep::Tick(inputs)) = self.steps.last_mut() {
+            inputs.push((player, input.clone()));
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum ReplayError {
+    /// The replay file couldn't be read or written
+    Io(io::Error),
+    /// The replay file isn't a valid replay
+    Parse(String),
+}
+
+impl fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "could not access replay: {}", e),
+            Self::Parse(e) => write!(f, "could not parse replay: {}", e),
+        }
+    }
+}
+
+impl Error for ReplayError {}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, process};
+
+    use crate::{ids::Id, pos::Vec2};
+
+    use super::*;
+
+    #[test]
+    fn inputs_are_loaded_exactly_as_saved() {
+        // These are parsed one bit off by serde_json's default float parser
+        let values = [
+            0.9252055592452639,
+            0.040687454916591204,
+            -0.21896385052362222,
+        ];
+        let mut replay = Replay::new(3, GameConfig::default());
+        replay.record(ReplayStep::Tick(Vec::new()));
+        for x in values {
+            let input = PlayerInput {
+                move_vec: Vec2 { x, y: -x },
+                ..Default::default()
+            };
+            replay.record_input(PlayerId::new(1), &input);
+        }
+
+        let path = env::temp_dir().join(format!("cell_game_inputs_{}.json", process::id()));
+        replay.save(&path).unwrap();
+        let loaded = Replay::load(&path);
+        fs::remove_file(&path).unwrap();
+
+        let loaded = loaded.unwrap();
+        let Some(ReplayStep::Tick(inputs)) = loaded.steps().first() else {
+            panic!("tick was not loaded");
+        };
+        assert_eq!(inputs.len(), values.len());
+        for ((player, input), x) in inputs.iter().zip(values) {
+            assert_eq!(player.value(), 1);
+            assert_eq!(input.move_vec.x.to_bits(), x.to_bits());
+            assert_eq!(input.move_vec.y.to_bits(), (-x).to_bits());
+        }
+    }
+}
@@ -0,0 +1,183 @@
+use std::{cell::RefCell, collections::HashMap, rc::Rc};
+
+use crate::{
+    client_connection::{ClientConnection, PlayerInput},
+    game_view::GameView,
+    ids::PlayerId,
+};
+
+use super::{
+    game_server::GameServer,
+    replay::{Replay, ReplayStep},
+    server_view::ServerView,
+};
+
+/// Plays a recorded game back tick by tick. The game is simulated again from
+/// the seed using the recorded inputs, so it can be watched from the view of
+/// any player, including bots.
+pub struct ReplayPlayer {
+    replay: Replay,
+    game: GameServer,
+    /// The index of the next step to play
+    next_step: usize,
+    /// How many ticks have been played
+    tick: usize,
+    /// The recorded inputs for the tick being played
+    inputs: Rc<RefCell<HashMap<PlayerId, PlayerInput>>>,
+}
+
+/// Gives the server whatever a player did in the recording
+struct ReplayConnection {
+    inputs: Rc<RefCell<HashMap<PlayerId, PlayerInput>>>,
+}
+
+impl ReplayPlayer {
+    pub fn new(replay: Replay) -> Self {
+        let game = GameServer::with_seed(replay.config().clone(), replay.seed());
+        Self {
+            replay,
+            game,
+            next_step: 0,
+            tick: 0,
+            inputs: Rc::new(RefCell::new(HashMap::new())),
+        }
+    }
+
+    pub fn replay(&self) -> &Replay {
+        &self.replay
+    }
+
+    /// The game as of the current tick
+    pub fn game(&self) -> &GameServer {
+        &self.game
+    }
+
+    /// How many ticks have been played
+    pub fn tick(&self) -> usize {
+        self.tick
+    }
+
+    /// Whether every recorded step has been played
+    pub fn finished(&self) -> bool {
+        self.next_step >= self.replay.steps().len()
+    }
+
+    /// Plays up to and including the next recorded tick. Returns false if the
+    /// replay had no ticks left.
+    pub fn step(&mut self) -> bool {
+        while let Some(step) = self.replay.steps().get(self.next_step) {
+            self.next_step += 1;
+            match step {
+                ReplayStep::Connect { name, bot } => {
+                    let conn = ReplayConnection {
+                        inputs: self.inputs.clone(),
+                    };
+                    self.game
+                        .connect_replayed(name.clone(), *bot, Box::new(conn));
+                }
+                ReplayStep::Disconnect(player) => {
+                    self.game.disconnect_player(*player);
+                }
+                ReplayStep::Respawn(player) => {
+                    self.game.respawn_player(*player);
+                }
+                ReplayStep::Tick(inputs) => {
+                    *self.inputs.borrow_mut() = inputs.iter().cloned().collect();
+                    self.game.tick();
+                    self.tick += 1;
+                    return true;
+                }
+            }
+        }
+        false
+    }
+
+    /// Moves to just after the given tick. Seeking backwards plays the game
+    /// again from the start.
+    pub fn seek(&mut self, tick: usize) {
+        if tick < self.tick {
+            *self = Self::new(self.replay.clone());
+        }
+        while self.tick < tick && self.step() {}
+    }
+
+    /// What a recorded player could see at the current tick. Returns `None`
+    /// if the player isn't connected at this point of the replay.
+    pub fn player_view(&mut self, player: PlayerId) -> Option<ServerView<'_>> {
+        self.game.player_view(player)
+    }
+}
+
+impl<'a> ClientConnection<'a> for ReplayConnection {
+    type V = ServerView<'a>;
+
+    fn on_tick(&mut self, view: Self::V) -> PlayerInput {
+        self.inputs
+            .borrow()
+            .get(&view.owner())
+            .cloned()
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs, process};
+
+    use crate::{bot::difficulty::Difficulty, test_support::small_config};
+
+    use super::*;
+
+    /// Records a game between bots that respawn when they die, with one bot
+    /// leaving partway through
+    fn record(ticks: usize) -> GameServer {
+        let mut game = GameServer::with_recording(small_config(), 11);
+        let bots: Vec<_> = (0..4)
+            .map(|i| game.connect_bot(format!("Bot {}", i), Difficulty::Hard))
+            .collect();
+        for tick in 0..ticks {
+            if tick == ticks / 2 {
+                game.disconnect_player(bots[0]);
+            }
+            game.tick();
+            game.respawn_dead_players();
+        }
+        game
+    }
+
+    #[test]
+    fn saved_replays_play_back_the_same_game() {
+        let game = record(300);
+        let path = env::temp_dir().join(format!("cell_game_replay_{}.json", process::id()));
+        game.replay().unwrap().save(&path).unwrap();
+        let replay = Replay::load(&path);
+        fs::remove_file(&path).unwrap();
+
+        let mut player = ReplayPlayer::new(replay.unwrap());
+        while player.step() {}
+        assert!(player.finished());
+        assert_eq!(player.tick(), 300);
+        assert!(player.game().cell_bits() == game.cell_bits());
+        for (played, recorded) in player.game().player_infos().iter().zip(game.player_infos()) {
+            assert!(played.id() == recorded.id());
+            assert_eq!(played.name(), recorded.name());
+        }
+    }
+
+    #[test]
+    fn seeking_matches_playing_forward() {
+        let replay = record(200).replay().unwrap().clone();
+        let mut forward = ReplayPlayer::new(replay.clone());
+        let mut cells = vec![forward.game().cell_bits()];
+        while forward.step() {
+            cells.push(forward.game().cell_bits());
+        }
+
+        let mut player = ReplayPlayer::new(replay);
+        for tick in [150, 50, 0, 120, 120, 200] {
+            player.seek(tick);
+            assert_eq!(player.tick(), tick);
+            assert!(player.game().cell_bits() == cells[tick], "tick {}", tick);
+        }
+    }
+}
@@ -1,32 +1,50 @@
 use crate::{
-    cells::{cell::Cell, food_cell::FoodCell, player_cell::PlayerCell},
+    cells::{
+        ejected_mass_cell::EjectedMassCell, food_cell::FoodCell, player_cell::PlayerCell,
+        virus_cell::VirusCell,
+    },
     game_view::GameView,
     ids::PlayerId,
+    leaderboard::{Leaderboard, LeaderboardEntry},
     player_info::PlayerInfo,
-    pos::Circle,
+    pos::{Circle, Rect},
+    spatial_index::IndexedCells,
 };
 
 pub struct ServerView<'a> {
-    players: &'a [PlayerCell],
-    food: &'a [FoodCell],
+    players: IndexedCells<'a, PlayerCell>,
+    food: IndexedCells<'a, FoodCell>,
+    ejected_mass: IndexedCells<'a, EjectedMassCell>,
+    viruses: IndexedCells<'a, VirusCell>,
     player_infos: &'a [PlayerInfo],
+    leaderboard: &'a Leaderboard,
     view_area: Circle,
+    world_bounds: Rect,
     owner: PlayerId,
 }
 
 impl<'a> ServerView<'a> {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
-        players: &'a Vec<PlayerCell>,
-        food: &'a Vec<FoodCell>,
+        players: IndexedCells<'a, PlayerCell>,
+        food: IndexedCells<'a, FoodCell>,
+        ejected_mass: IndexedCells<'a, EjectedMassCell>,
+        viruses: IndexedCells<'a, VirusCell>,
         player_infos: &'a Vec<PlayerInfo>,
+        leaderboard: &'a Leaderboard,
         view_area: Circle,
+        world_bounds: Rect,
         owner: PlayerId,
     ) -> Self {
         Self {
             players,
             food,
+            ejected_mass,
+            viruses,
             player_infos,
+            leaderboard,
             view_area,
+            world_bounds,
             owner,
         }
     }
@@ -34,11 +52,19 @@ impl<'a> ServerView<'a> {
 
 impl GameView for ServerView<'_> {
     fn player_cells(&self) -> impl Iterator<Item = PlayerCell> {
-        self.filter_visible_cells(self.players).cloned()
+        self.players.overlapping(self.view_area).cloned()
     }
 
     fn food_cells(&self) -> impl Iterator<Item = FoodCell> {
-        self.filter_visible_cells(self.food).cloned()
+        self.food.overlapping(self.view_area).cloned()
+    }
+
+    fn ejected_mass_cells(&self) -> impl Iterator<Item = EjectedMassCell> {
+        self.ejected_mass.overlapping(self.view_area).cloned()
+    }
+
+    fn virus_cells(&self) -> impl Iterator<Item = VirusCell> {
+        self.viruses.overlapping(self.view_area).cloned()
     }
 
     fn player_infos(&self) -> impl Iterator<Item = &PlayerInfo> {
@@ -49,16 +75,15 @@ impl GameView for ServerView<'_> {
         self.view_area
     }
 
+    fn world_bounds(&self) -> Rect {
+        self.world_bounds
+    }
+
     fn owner(&self) -> PlayerId {
         self.owner
     }
-}
 
-impl<'a> ServerView<'a> {
-    #[inline]
-    fn filter_visible_cells<T: Cell>(&'a self, cells: &'a [T]) -> impl Iterator<Item = &'a T> {
-        cells
-            .iter()
-            .filter(|cell| cell.hitbox().overlaps_circle(self.view_area))
+    fn leaderboard(&self) -> impl Iterator<Item = LeaderboardEntry> {
+        self.leaderboard.entries_for(self.owner)
     }
 }
@@ -0,0 +1,83 @@
+use rand::RngCore;
+
+use crate::{
+    cells::{cell::Cell, player_cell::PlayerCell},
+    ids::PlayerId,
+    pos::{Point, Rect},
+};
+
+/// Decides where new player cells are spawned. Game modes can supply their
+/// own strategy, such as spawning players at their team's base.
+pub trait SpawnStrategy {
+    /// Picks a position within `bounds` for a new cell of `spawn_mass` that
+    /// belongs to `player`. `players` holds every player cell currently in the
+    /// game.
+    fn spawn_point(
+        &mut self,
+        player: PlayerId,
+        spawn_mass: f64,
+        bounds: Rect,
+        players: &[PlayerCell],
+        rng: &mut dyn RngCore,
+    ) -> Point;
+}
+
+/// Spawns players at random positions away from any cells that could eat
+/// them.
+pub struct SafeSpawn {
+    /// How many random positions to try before settling for the safest one
+    pub attempts: usize,
+    /// How many times its radius a larger cell must be from a spawn point for
+    /// the spawn point to be safe
+    pub danger_radius_multiplier: f64,
+}
+
+impl SafeSpawn {
+    /// How far a point is from the edge of the danger zone of the closest
+    /// larger cell. Negative if the point is inside a danger zone.
+    fn safety(&self, pos: Point, spawn_mass: f64, players: &[PlayerCell]) -> f64 {
+        players
+            .iter()
+            .filter(|cell| cell.mass() > spawn_mass)
+            .map(|cell| cell.pos().dist_to(pos) - cell.radius() * self.danger_radius_multiplier)
+            .fold(f64::INFINITY, f64::min)
+    }
+}
+
+impl Default for SafeSpawn {
+    fn default() -> Self {
+        Self {
+            attempts: 20,
+            danger_radius_multiplier: 3.0,
+        }
+    }
+}
+
+impl SpawnStrategy for SafeSpawn {
+    /// Returns the first sampled position that is outside of every danger
+    /// zone. If the map is too crowded for any of the samples to be safe, the
+    /// one furthest from danger is used instead.
+    fn spawn_point(
+        &mut self,
+        _player: PlayerId,
+        spawn_mass: f64,
+        bounds: Rect,
+        players: &[PlayerCell],
+        rng: &mut dyn RngCore,
+    ) -> Point {
+        let mut safest = bounds.center();
+        let mut safest_safety = f64::NEG_INFINITY;
+        for _ in 0..self.attempts.max(1) {
+            let candidate = bounds.random_point(rng);
+            let safety = self.safety(candidate, spawn_mass, players);
+            if safety >= 0.0 {
+                return candidate;
+            }
+            if safety > safest_safety {
+                safest = candidate;
+                safest_safety = safety;
+            }
+        }
+        safest
+    }
+}
@@ -0,0 +1,128 @@
+use crate::{
+    cells::cell::Cell,
+    pos::{Circle, Point, Rect},
+};
+
+/// Buckets cells by position into a uniform grid of square tiles, so that
+/// cells near an area can be found without checking every cell in the game.
+/// The index stores positions in a slice of cells rather than the cells
+/// themselves, so it has to be rebuilt whenever the slice changes.
+pub struct SpatialIndex {
+    bounds: Rect,
+    tile_size: f64,
+    columns: usize,
+    rows: usize,
+    /// The indices of every cell, sorted by tile in row-major order so that
+    /// each row of tiles in a query is one contiguous slice
+    entries: Vec<usize>,
+    /// Where each tile's cells start in `entries`. Has one more element than
+    /// there are tiles, so that tile `i` spans `tile_starts[i]..tile_starts[i + 1]`.
+    tile_starts: Vec<usize>,
+    /// The radius of the largest cell in the index. Queries are expanded by
+    /// this so that cells centered outside of an area but overlapping it are
+    /// still found.
+    max_radius: f64,
+}
+
+impl SpatialIndex {
+    /// `tile_size` must be positive
+    pub fn new(bounds: Rect, tile_size: f64) -> Self {
+        let columns = ((bounds.width / tile_size).ceil() as usize).max(1);
+        let rows = ((bounds.height / tile_size).ceil() as usize).max(1);
+        Self {
+            bounds,
+            tile_size,
+            columns,
+            rows,
+            entries: Vec::new(),
+            tile_starts: vec![0; columns * rows + 1],
+            max_radius: 0.0,
+        }
+    }
+
+    pub fn from_cells<T: Cell>(bounds: Rect, tile_size: f64, cells: &[T]) -> Self {
+        let mut index = Self::new(bounds, tile_size);
+        index.rebuild(cells);
+        index
+    }
+
+    /// Replaces the contents of the index with the positions of `cells`
+    pub fn rebuild<T: Cell>(&mut self, cells: &[T]) {
+        let tiles: Vec<usize> = cells
+            .iter()
+            .map(|cell| self.tile_index(cell.pos()))
+            .collect();
+
+        // Count the cells in each tile, then turn the counts into the
+        // positions that each tile ends at
+        self.tile_starts.iter_mut().for_each(|start| *start = 0);
+        for &tile in tiles.iter() {
+            self.tile_starts[tile + 1] += 1;
+        }
+        for i in 1..self.tile_starts.len() {
+            self.tile_starts[i] += self.tile_starts[i - 1];
+        }
+
+        let mut next_slot = self.tile_starts.clone();
+        self.entries.clear();
+        self.entries.resize(cells.len(), 0);
+        for (i, &tile) in tiles.iter().enumerate() {
+            self.entries[next_slot[tile]] = i;
+            next_slot[tile] += 1;
+        }
+
+        self.max_radius = cells.iter().map(|cell| cell.radius()).fold(0.0, f64::max);
+    }
+
+    /// The indices of every cell that could overlap `area`. This can include
+    /// cells that don't overlap it, so results still need to be checked.
+    pub fn near(&self, area: Circle) -> impl Iterator<Item = usize> + '_ {
+        let search_area = Circle {
+            center: area.center,
+            radius: area.radius + self.max_radius,
+        }
+        .bounding_rect();
+        let (min_column, min_row) = self.tile_coords(search_area.top_left);
+        let (max_column, max_row) = self.tile_coords(search_area.bottom_right());
+        (min_row..=max_row).flat_map(move |row| {
+            let first_tile = row * self.columns + min_column;
+            let last_tile = row * self.columns + max_column;
+            self.entries[self.tile_starts[first_tile]..self.tile_starts[last_tile + 1]]
+                .iter()
+                .copied()
+        })
+    }
+
+    fn tile_index(&self, pos: Point) -> usize {
+        let (column, row) = self.tile_coords(pos);
+        row * self.columns + column
+    }
+
+    /// The column and row of the tile containing a position. Positions
+    /// outside of the bounds are clamped to the closest tile.
+    fn tile_coords(&self, pos: Point) -> (usize, usize) {
+        let column = ((pos.x - self.bounds.min_x()) / self.tile_size).max(0.0) as usize;
+        let row = ((pos.y - self.bounds.min_y()) / self.tile_size).max(0.0) as usize;
+        (column.min(self.columns - 1), row.min(self.rows - 1))
+    }
+}
+
+/// A slice of cells together with an up to date index of their positions
+pub struct IndexedCells<'a, T> {
+    cells: &'a [T],
+    index: &'a SpatialIndex,
+}
+
+impl<'a, T: Cell> IndexedCells<'a, T> {
+    pub fn new(cells: &'a [T], index: &'a SpatialIndex) -> Self {
+        Self { cells, index }
+    }
+
+    /// Every cell whose hitbox overlaps `area`
+    pub fn overlapping(&self, area: Circle) -> impl Iterator<Item = &'a T> + '_ {
+        self.index
+            .near(area)
+            .map(|i| &self.cells[i])
+            .filter(move |cell| cell.hitbox().overlaps_circle(area))
+    }
+}
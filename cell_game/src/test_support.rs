@@ -0,0 +1,15 @@
+//! Settings shared by tests across the workspace. Other crates get this with
+//! the `test-support` feature.
+
+use crate::server::game_config::GameConfig;
+
+/// A small, crowded arena, so that players run into food, viruses and each
+/// other within a few hundred ticks
+pub fn small_config() -> GameConfig {
+    GameConfig {
+        width: 800.0,
+        height: 600.0,
+        food_amount: 200,
+        ..GameConfig::default()
+    }
+}
@@ -0,0 +1,37 @@
+use std::iter;
+
+use cell_game::{
+    cells::{food_cell::FoodCell, player_cell::PlayerCell},
+    game_view::GameView,
+    protocol::messages::ViewFrame,
+    server::server_view::ServerView,
+};
+
+/// A cell that is appearing in or disappearing from the view
+pub struct FadingCell<T> {
+    pub cell: T,
+    /// How much of the cell is shown, from 0 for none of it to 1 for all of
+    /// it
+    pub fade: f64,
+}
+
+/// A view that knows which cells are entering or leaving it, so that they can
+/// be animated instead of popping in and out. Views of a single tick have
+/// nothing to animate, so by default no cells are fading.
+pub trait AnimatedView: GameView {
+    /// Player cells that are entering or leaving the view. These aren't
+    /// included in `player_cells`.
+    fn fading_player_cells(&self) -> impl Iterator<Item = FadingCell<PlayerCell>> {
+        iter::empty()
+    }
+
+    /// Food that is entering or leaving the view. These aren't included in
+    /// `food_cells`.
+    fn fading_food_cells(&self) -> impl Iterator<Item = FadingCell<FoodCell>> {
+        iter::empty()
+    }
+}
+
+impl AnimatedView for ServerView<'_> {}
+
+impl AnimatedView for ViewFrame {}
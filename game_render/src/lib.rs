@@ -0,0 +1,5 @@
+pub mod animated_view;
+pub mod render_backend;
+pub mod scene_renderer;
+pub mod svg_backend;
+pub mod view_scaler;
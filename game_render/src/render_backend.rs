@@ -0,0 +1,55 @@
+use cell_game::pos::{Circle, Point, Rect};
+
+/// Something that the game can be drawn on. Positions and sizes are in
+/// pixels, with the origin in the top left corner. Colors are CSS color
+/// strings.
+pub trait RenderBackend {
+    /// The area that can be drawn on
+    fn viewport(&self) -> Rect;
+    /// Erases everything that has been drawn
+    fn clear(&mut self);
+    fn fill_circle(&mut self, circle: Circle, color: &str);
+    fn stroke_circle(&mut self, circle: Circle, line_width: f64, color: &str);
+    fn fill_rect(&mut self, rect: Rect, color: &str);
+    fn stroke_rect(&mut self, rect: Rect, line_width: f64, color: &str);
+    fn text(&mut self, text: &str, pos: Point, style: &TextStyle);
+    /// Only draws inside `clip` until this is called again with `None`
+    fn set_clip(&mut self, clip: Option<Rect>);
+}
+
+pub struct TextStyle<'a> {
+    /// The height of the font in pixels
+    pub size: f64,
+    pub bold: bool,
+    pub monospace: bool,
+    pub color: &'a str,
+    pub align: TextAlign,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum TextAlign {
+    /// The position is the left end of the text's baseline
+    Left,
+    /// The position is the center of the text
+    Center,
+}
+
+impl TextStyle<'_> {
+    /// The font as a CSS font shorthand, such as `bold 20px sans-serif`
+    pub fn css_font(&self) -> String {
+        format!(
+            "{}{}px {}",
+            if self.bold { "bold " } else { "" },
+            self.size,
+            self.font_family()
+        )
+    }
+
+    pub fn font_family(&self) -> &'static str {
+        if self.monospace {
+            "monospace"
+        } else {
+            "sans-serif"
+        }
+    }
+}
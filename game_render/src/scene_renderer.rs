@@ -0,0 +1,259 @@
+use cell_game::{
+    cells::{cell::Cell, player_cell::PlayerCell},
+    game_view::GameView,
+    player_info::PlayerInfo,
+    pos::{Circle, Point, Rect},
+};
+
+use super::{
+    animated_view::AnimatedView,
+    render_backend::{RenderBackend, TextAlign, TextStyle},
+    view_scaler::ViewScaler,
+};
+
+/// Draws views of the game on a render backend
+pub struct SceneRenderer {
+    view_scaler: Option<ViewScaler>,
+}
+
+impl SceneRenderer {
+    const LEADERBOARD_WIDTH: f64 = 220.0;
+    const LEADERBOARD_ROW_HEIGHT: f64 = 24.0;
+    const LEADERBOARD_MARGIN: f64 = 10.0;
+    /// The length of the longest side of the minimap
+    const MINIMAP_SIZE: f64 = 180.0;
+    const MINIMAP_MARGIN: f64 = 10.0;
+    /// The minimap is hidden on viewports smaller than this, where it would
+    /// cover too much of the game
+    const MINIMAP_MIN_VIEWPORT_WIDTH: f64 = 640.0;
+    const MINIMAP_MIN_VIEWPORT_HEIGHT: f64 = 480.0;
+    const OVERLAY_BACKGROUND: &'static str = "rgba(0, 0, 0, 0.4)";
+
+    pub fn new() -> Self {
+        Self { view_scaler: None }
+    }
+
+    /// The scaler used for the last rendered view
+    pub fn view_scaler(&self) -> Option<&ViewScaler> {
+        self.view_scaler.as_ref()
+    }
+
+    pub fn render(&mut self, backend: &mut impl RenderBackend, game: &impl AnimatedView) {
+        backend.clear();
+
+        let viewport = backend.viewport();
+        let scaler = ViewScaler::new(game, viewport.width, viewport.height);
+
+        Self::render_cells(backend, &scaler, game);
+        Self::render_leaderboard(backend, game);
+        Self::render_minimap(backend, game);
+
+        self.view_scaler = Some(scaler);
+    }
+
+    fn render_cells(
+        backend: &mut impl RenderBackend,
+        scaler: &ViewScaler,
+        game: &impl AnimatedView,
+    ) {
+        for p in game.player_cells() {
+            Self::render_player_cell(
+                backend,
+                scaler,
+                &p,
+                game.player_infos().find(|i| i.id() == p.owner()).unwrap(),
+                1.0,
+            );
+        }
+        for fading in game.fading_player_cells() {
+            // Cells can leave along with their owner's info
+            if let Some(info) = game.player_infos().find(|i| i.id() == fading.cell.owner()) {
+                Self::render_player_cell(backend, scaler, &fading.cell, info, fading.fade);
+            }
+        }
+
+        for f in game.food_cells() {
+            backend.fill_circle(
+                scaler.game_to_canvas_circle(f.hitbox()),
+                &f.color().to_string(),
+            );
+        }
+        for fading in game.fading_food_cells() {
+            backend.fill_circle(
+                scaler.game_to_canvas_circle(fading.cell.hitbox().scale_centered(fading.fade)),
+                &fading.cell.color().to_string(),
+            );
+        }
+
+        for e in game.ejected_mass_cells() {
+            backend.fill_circle(
+                scaler.game_to_canvas_circle(e.hitbox()),
+                &e.color().to_string(),
+            );
+        }
+
+        // Viruses are drawn last so that smaller cells can hide under them
+        for v in game.virus_cells() {
+            backend.fill_circle(
+                scaler.game_to_canvas_circle(v.hitbox()),
+                &v.color().to_string(),
+            );
+        }
+    }
+
+    /// Lists the top players in the top right corner of the viewport, with the
+    /// view owner's row highlighted
+    fn render_leaderboard(backend: &mut impl RenderBackend, game: &impl GameView) {
+        let entries: Vec<_> = game.leaderboard().collect();
+        if entries.is_empty() {
+            return;
+        }
+        let viewport = backend.viewport();
+        let left = viewport.max_x() - Self::LEADERBOARD_WIDTH - Self::LEADERBOARD_MARGIN;
+        let top = viewport.min_y() + Self::LEADERBOARD_MARGIN;
+        let padding = Self::LEADERBOARD_ROW_HEIGHT / 2.0;
+
+        backend.fill_rect(
+            Rect {
+                top_left: Point { x: left, y: top },
+                width: Self::LEADERBOARD_WIDTH,
+                height: Self::LEADERBOARD_ROW_HEIGHT * (entries.len() + 1) as f64 + padding,
+            },
+            Self::OVERLAY_BACKGROUND,
+        );
+
+        backend.text(
+            "Leaderboard",
+            Point {
+                x: left + padding,
+                y: top + Self::LEADERBOARD_ROW_HEIGHT,
+            },
+            &TextStyle {
+                size: 20.0,
+                bold: true,
+                monospace: false,
+                color: "white",
+                align: TextAlign::Left,
+            },
+        );
+
+        for (row, entry) in entries.iter().enumerate() {
+            let name = game
+                .player_infos()
+                .find(|info| info.id() == entry.player)
+                .map_or("", |info| info.name());
+            let color = if entry.player == game.owner() {
+                "#ffd700"
+            } else {
+                "white"
+            };
+            backend.text(
+                &format!("{}. {} ({:.0})", entry.rank, name, entry.mass),
+                Point {
+                    x: left + padding,
+                    y: top + Self::LEADERBOARD_ROW_HEIGHT * (row + 2) as f64,
+                },
+                &TextStyle {
+                    size: 16.0,
+                    bold: false,
+                    monospace: false,
+                    color,
+                    align: TextAlign::Left,
+                },
+            );
+        }
+    }
+
+    /// Draws the whole game area in the bottom right corner of the viewport,
+    /// with the owner's cells and their view area on it
+    fn render_minimap(backend: &mut impl RenderBackend, game: &impl GameView) {
+        let viewport = backend.viewport();
+        if viewport.width < Self::MINIMAP_MIN_VIEWPORT_WIDTH
+            || viewport.height < Self::MINIMAP_MIN_VIEWPORT_HEIGHT
+        {
+            return;
+        }
+        let bounds = game.world_bounds();
+        let scale = Self::MINIMAP_SIZE / bounds.width.max(bounds.height);
+        let minimap = Rect {
+            top_left: Point {
+                x: viewport.max_x() - bounds.width * scale - Self::MINIMAP_MARGIN,
+                y: viewport.max_y() - bounds.height * scale - Self::MINIMAP_MARGIN,
+            },
+            width: bounds.width * scale,
+            height: bounds.height * scale,
+        };
+        let to_minimap = |Point { x, y }: Point| Point {
+            x: minimap.min_x() + (x - bounds.min_x()) * scale,
+            y: minimap.min_y() + (y - bounds.min_y()) * scale,
+        };
+
+        backend.fill_rect(minimap, Self::OVERLAY_BACKGROUND);
+        backend.stroke_rect(minimap, 1.0, "white");
+
+        // The view area can reach past the edges of the game, so it's clipped
+        // to the minimap
+        backend.set_clip(Some(minimap));
+        let view_area = game.view_area();
+        backend.stroke_circle(
+            Circle {
+                center: to_minimap(view_area.center),
+                radius: view_area.radius * scale,
+            },
+            1.0,
+            "white",
+        );
+
+        if let Some(info) = game.player_infos().find(|info| info.id() == game.owner()) {
+            let color = info.color().to_string();
+            for cell in game
+                .player_cells()
+                .filter(|cell| cell.owner() == game.owner())
+            {
+                backend.fill_circle(
+                    Circle {
+                        center: to_minimap(cell.pos()),
+                        // Keep small cells visible
+                        radius: (cell.radius() * scale).max(2.0),
+                    },
+                    &color,
+                );
+            }
+        }
+        backend.set_clip(None);
+    }
+
+    /// Draws a cell `scale` times its size. Names are only drawn on cells
+    /// that are mostly shown, so they don't spill out of shrinking cells.
+    fn render_player_cell(
+        backend: &mut impl RenderBackend,
+        scaler: &ViewScaler,
+        cell: &PlayerCell,
+        info: &PlayerInfo,
+        scale: f64,
+    ) {
+        backend.fill_circle(
+            scaler.game_to_canvas_circle(cell.hitbox().scale_centered(scale)),
+            &info.color().to_string(),
+        );
+        if scale > 0.5 {
+            backend.text(
+                info.name(),
+                scaler.game_to_canvas_pos(cell.pos()),
+                &TextStyle {
+                    size: 25.0,
+                    bold: false,
+                    monospace: false,
+                    color: "black",
+                    align: TextAlign::Center,
+                },
+            );
+        }
+    }
+}
+
+impl Default for SceneRenderer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,160 @@
+use std::{fmt::Write, fs, io, path::Path};
+
+use cell_game::pos::{Circle, Point, Rect};
+
+use super::render_backend::{RenderBackend, TextAlign, TextStyle};
+
+/// Draws into an SVG document, so frames can be rendered without a browser.
+/// Coordinates are written with two decimal places, so the same frame always
+/// produces the same file and frames can be compared byte for byte.
+pub struct SvgBackend {
+    width: f64,
+    height: f64,
+    body: String,
+    /// How many clip paths have been defined, used to give each a unique id
+    clip_count: usize,
+    /// Whether elements are currently being written into a clipped group
+    clipped: bool,
+}
+
+impl SvgBackend {
+    pub fn new(width: f64, height: f64) -> Self {
+        Self {
+            width,
+            height,
+            body: String::new(),
+            clip_count: 0,
+            clipped: false,
+        }
+    }
+
+    /// The finished document
+    pub fn to_svg(&self) -> String {
+        format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{w:.2}\" height=\"{h:.2}\" viewBox=\"0 0 {w:.2} {h:.2}\">\n{}{}</svg>\n",
+            self.body,
+            if self.clipped { "</g>\n" } else { "" },
+            w = self.width,
+            h = self.height,
+        )
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        fs::write(path, self.to_svg())
+    }
+}
+
+impl RenderBackend for SvgBackend {
+    fn viewport(&self) -> Rect {
+        Rect {
+            top_left: Point { x: 0.0, y: 0.0 },
+            width: self.width,
+            height: self.height,
+        }
+    }
+
+    fn clear(&mut self) {
+        self.body.clear();
+        self.clip_count = 0;
+        self.clipped = false;
+    }
+
+    fn fill_circle(&mut self, circle: Circle, color: &str) {
+        let _ = writeln!(
+            self.body,
+            "<circle cx=\"{:.2}\" cy=\"{:.2}\" r=\"{:.2}\" fill=\"{}\"/>",
+            circle.center.x,
+            circle.center.y,
+            circle.radius,
+            escape(color)
+        );
+    }
+
+    fn stroke_circle(&mut self, circle: Circle, line_width: f64, color: &str) {
+        let _ = writeln!(
+            self.body,
+            "<circle cx=\"{:.2}\" cy=\"{:.2}\" r=\"{:.2}\" fill=\"none\" stroke=\"{}\" stroke-width=\"{:.2}\"/>",
+            circle.center.x,
+            circle.center.y,
+            circle.radius,
+            escape(color),
+            line_width
+        );
+    }
+
+    fn fill_rect(&mut self, rect: Rect, color: &str) {
+        let _ = writeln!(
+            self.body,
+            "<rect x=\"{:.2}\" y=\"{:.2}\" width=\"{:.2}\" height=\"{:.2}\" fill=\"{}\"/>",
+            rect.top_left.x,
+            rect.top_left.y,
+            rect.width,
+            rect.height,
+            escape(color)
+        );
+    }
+
+    fn stroke_rect(&mut self, rect: Rect, line_width: f64, color: &str) {
+        let _ = writeln!(
+            self.body,
+            "<rect x=\"{:.2}\" y=\"{:.2}\" width=\"{:.2}\" height=\"{:.2}\" fill=\"none\" stroke=\"{}\" stroke-width=\"{:.2}\"/>",
+            rect.top_left.x,
+            rect.top_left.y,
+            rect.width,
+            rect.height,
+            escape(color),
+            line_width
+        );
+    }
+
+    fn text(&mut self, text: &str, pos: Point, style: &TextStyle) {
+        let anchor = match style.align {
+            TextAlign::Left => "",
+            TextAlign::Center => " text-anchor=\"middle\" dominant-baseline=\"central\"",
+        };
+        let _ = writeln!(
+            self.body,
+            "<text x=\"{:.2}\" y=\"{:.2}\" font-family=\"{}\" font-size=\"{:.2}\"{} fill=\"{}\"{}>{}</text>",
+            pos.x,
+            pos.y,
+            style.font_family(),
+            style.size,
+            if style.bold { " font-weight=\"bold\"" } else { "" },
+            escape(style.color),
+            anchor,
+            escape(text)
+        );
+    }
+
+    fn set_clip(&mut self, clip: Option<Rect>) {
+        if self.clipped {
+            self.body.push_str("</g>\n");
+            self.clipped = false;
+        }
+        if let Some(rect) = clip {
+            let id = self.clip_count;
+            self.clip_count += 1;
+            let _ = writeln!(
+                self.body,
+                "<clipPath id=\"clip{}\"><rect x=\"{:.2}\" y=\"{:.2}\" width=\"{:.2}\" height=\"{:.2}\"/></clipPath>\n<g clip-path=\"url(#clip{})\">",
+                id, rect.top_left.x, rect.top_left.y, rect.width, rect.height, id
+            );
+            self.clipped = true;
+        }
+    }
+}
+
+/// Escapes the characters that can't appear as-is in SVG text and attributes
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
@@ -2,9 +2,8 @@ use cell_game::{
     game_view::GameView,
     pos::{Circle, Point, Rect},
 };
-use web_sys::HtmlCanvasElement;
 
-/// Scales coordinates between
+/// Scales coordinates between the game and the area it's drawn on
 pub struct ViewScaler {
     visible_game_area: Rect,
     canvas_to_game_scale: f64,
@@ -16,14 +15,16 @@ impl ViewScaler {
     /// canvas if the game isn't local.
     const CAMERA_AREA_SCALE: f64 = 0.9;
 
-    pub fn new(game_view: &impl GameView, cvs: &HtmlCanvasElement) -> Self {
+    /// Fits the view area of `game_view` to a canvas of the given size in
+    /// pixels
+    pub fn new(game_view: &impl GameView, width: f64, height: f64) -> Self {
         let visible_game_area = game_view
             .view_area()
-            .fit_rect_within_circle(cvs.width() as f64 / cvs.height() as f64)
+            .fit_rect_within_circle(width / height)
             .scale_centered(Self::CAMERA_AREA_SCALE);
         Self {
             visible_game_area,
-            canvas_to_game_scale: cvs.width() as f64 / visible_game_area.width,
+            canvas_to_game_scale: width / visible_game_area.width,
         }
     }
 
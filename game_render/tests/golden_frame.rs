@@ -0,0 +1,43 @@
+//! Renders a frame of a seeded game and compares it with a saved copy, so
+//! that any change to how scenes are drawn shows up as a failing test. After
+//! an intended change, run with `UPDATE_GOLDEN=1` to save the new frame and
+//! check the diff of `tests/fixtures/frame.svg`.
+
+use std::{env, fs, path::PathBuf};
+
+use cell_game::{
+    bot::difficulty::Difficulty, server::game_server::GameServer, test_support::small_config,
+};
+use game_render::{scene_renderer::SceneRenderer, svg_backend::SvgBackend};
+
+fn render_frame() -> String {
+    let mut game = GameServer::with_seed(small_config(), 2024);
+    let bots: Vec<_> = (0..6)
+        .map(|i| game.connect_bot(format!("Bot {}", i), Difficulty::Normal))
+        .collect();
+    for _ in 0..240 {
+        game.tick();
+        game.respawn_dead_players();
+    }
+
+    let view = game.player_view(bots[0]).expect("bot is connected");
+    let mut backend = SvgBackend::new(1280.0, 720.0);
+    SceneRenderer::new().render(&mut backend, &view);
+    backend.to_svg()
+}
+
+#[test]
+fn frame_matches_fixture() {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/frame.svg");
+    let frame = render_frame();
+    if env::var_os("UPDATE_GOLDEN").is_some() {
+        fs::write(&path, &frame).unwrap();
+        return;
+    }
+    let fixture = fs::read_to_string(&path).unwrap();
+    assert!(
+        frame == fixture,
+        "rendered frame differs from {}; rerun with UPDATE_GOLDEN=1 if the change is intended",
+        path.display()
+    );
+}
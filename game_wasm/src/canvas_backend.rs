@@ -0,0 +1,111 @@
+use std::f64;
+
+use cell_game::pos::{Circle, Point, Rect};
+use game_render::render_backend::{RenderBackend, TextAlign, TextStyle};
+use wasm_bindgen::{JsCast, JsValue};
+
+use crate::web_utils;
+
+/// Draws on the page's HTML canvas
+pub struct CanvasBackend {
+    cvs: web_sys::HtmlCanvasElement,
+    ctx: web_sys::CanvasRenderingContext2d,
+}
+
+impl CanvasBackend {
+    pub fn new() -> Self {
+        Self {
+            cvs: web_utils::canvas(),
+            ctx: web_utils::canvas_rendering_context_2d(),
+        }
+    }
+
+    /// Resizes the canvas to match the size it's shown at on the page
+    pub fn fit_to_element(&self) {
+        let rect = self
+            .cvs
+            .clone()
+            .dyn_into::<web_sys::Element>()
+            .unwrap()
+            .get_bounding_client_rect();
+        self.cvs.set_width(rect.width() as u32);
+        self.cvs.set_height(rect.height() as u32);
+    }
+
+    fn circle_path(
+        &self,
+        Circle {
+            center: Point { x, y },
+            radius,
+        }: Circle,
+    ) {
+        self.ctx.begin_path();
+        self.ctx
+            .arc(x, y, radius, 0.0, f64::consts::TAU)
+            .expect("could not draw arc");
+    }
+}
+
+impl RenderBackend for CanvasBackend {
+    fn viewport(&self) -> Rect {
+        Rect::new(0.0, 0.0, self.cvs.width() as f64, self.cvs.height() as f64)
+    }
+
+    fn clear(&mut self) {
+        self.ctx
+            .clear_rect(0.0, 0.0, self.cvs.width() as f64, self.cvs.height() as f64);
+    }
+
+    fn fill_circle(&mut self, circle: Circle, color: &str) {
+        self.circle_path(circle);
+        self.ctx.set_fill_style(&JsValue::from_str(color));
+        self.ctx.fill();
+    }
+
+    fn stroke_circle(&mut self, circle: Circle, line_width: f64, color: &str) {
+        self.circle_path(circle);
+        self.ctx.set_line_width(line_width);
+        self.ctx.set_stroke_style(&JsValue::from_str(color));
+        self.ctx.stroke();
+    }
+
+    fn fill_rect(&mut self, rect: Rect, color: &str) {
+        self.ctx.set_fill_style(&JsValue::from_str(color));
+        self.ctx
+            .fill_rect(rect.min_x(), rect.min_y(), rect.width, rect.height);
+    }
+
+    fn stroke_rect(&mut self, rect: Rect, line_width: f64, color: &str) {
+        self.ctx.set_line_width(line_width);
+        self.ctx.set_stroke_style(&JsValue::from_str(color));
+        self.ctx
+            .stroke_rect(rect.min_x(), rect.min_y(), rect.width, rect.height);
+    }
+
+    fn text(&mut self, text: &str, Point { x, y }: Point, style: &TextStyle) {
+        let (align, baseline) = match style.align {
+            TextAlign::Left => ("left", "alphabetic"),
+            TextAlign::Center => ("center", "middle"),
+        };
+        self.ctx.set_font(&style.css_font());
+        self.ctx.set_text_align(align);
+        self.ctx.set_text_baseline(baseline);
+        self.ctx.set_fill_style(&JsValue::from_str(style.color));
+        self.ctx
+            .fill_text(text, x, y)
+            .expect("could not render text")
+    }
+
+    fn set_clip(&mut self, clip: Option<Rect>) {
+        // The canvas can only narrow its clip, so the state from before
+        // clipping is saved to be restored when the clip is removed
+        self.ctx.restore();
+        if let Some(rect) = clip {
+            self.ctx.save();
+            self.ctx.begin_path();
+            self.ctx
+                .rect(rect.min_x(), rect.min_y(), rect.width, rect.height);
+            self.ctx.clip();
+        }
+    }
+}
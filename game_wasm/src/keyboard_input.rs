@@ -0,0 +1,267 @@
+use std::cell::RefCell;
+
+use cell_game::client_connection::PlayerInput;
+
+/// An action that can be bound to a key
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum KeyAction {
+    Split,
+    Eject,
+    Freeze,
+}
+
+/// Which keys trigger each action. Keys are `KeyboardEvent.code` values, so
+/// bindings stay in the same place on the keyboard whatever the layout.
+pub struct KeyBindings {
+    pub split: String,
+    pub eject: String,
+    pub freeze: String,
+}
+
+impl KeyBindings {
+    /// The default bindings, with any overridden by the page's query string,
+    /// such as `?split=KeyQ&eject=KeyE`
+    pub fn from_query(search: &str) -> Self {
+        let mut bindings = Self::default();
+        for (name, code) in search
+            .trim_start_matches('?')
+            .split('&')
+            .filter_map(|param| param.split_once('='))
+        {
+            match name {
+                "split" => bindings.split = code.to_owned(),
+                "eject" => bindings.eject = code.to_owned(),
+                "freeze" => bindings.freeze = code.to_owned(),
+                _ => {}
+            }
+        }
+        bindings
+    }
+
+    pub fn action(&self, code: &str) -> Option<KeyAction> {
+        if code == self.split {
+            Some(KeyAction::Split)
+        } else if code == self.eject {
+            Some(KeyAction::Eject)
+        } else if code == self.freeze {
+            Some(KeyAction::Freeze)
+        } else {
+            None
+        }
+    }
+}
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        Self {
+            split: "Space".to_owned(),
+            eject: "KeyW".to_owned(),
+            freeze: "KeyS".to_owned(),
+        }
+    }
+}
+
+/// Tracks which bound keys are pressed and turns them into player inputs.
+/// Splitting happens once per key press, ejecting repeats for as long as the
+/// key is held, and freezing lasts until the key is released.
+pub struct KeyboardInput {
+    bindings: KeyBindings,
+    /// Whether split was pressed since the last input was written
+    split_pressed: bool,
+    /// When the next eject should happen if the eject key is held, in
+    /// milliseconds
+    next_eject: Option<f64>,
+    freeze_held: bool,
+}
+
+impl KeyboardInput {
+    /// How often mass is ejected while the eject key is held, in
+    /// milliseconds. The browser's own key repeat is ignored, since its delay
+    /// and rate depend on the player's system settings.
+    const EJECT_REPEAT_INTERVAL: f64 = 80.0;
+
+    pub fn new(bindings: KeyBindings) -> Self {
+        Self {
+            bindings,
+            split_pressed: false,
+            next_eject: None,
+            freeze_held: false,
+        }
+    }
+
+    /// Returns whether the key is bound, in which case the browser shouldn't
+    /// handle it as well
+    pub fn key_down(&mut self, code: &str, repeat: bool, now: f64) -> bool {
+        let Some(action) = self.bindings.action(code) else {
+            return false;
+        };
+        if !repeat {
+            match action {
+                KeyAction::Split => self.split_pressed = true,
+                KeyAction::Eject => self.next_eject = Some(now),
+                KeyAction::Freeze => self.freeze_held = true,
+            }
+        }
+        true
+    }
+
+    pub fn key_up(&mut self, code: &str) {
+        match self.bindings.action(code) {
+            Some(KeyAction::Split) | None => {}
+            Some(KeyAction::Eject) => self.next_eject = None,
+            Some(KeyAction::Freeze) => self.freeze_held = false,
+        }
+    }
+
+    /// Releases every key, for when the page loses focus and key releases
+    /// can no longer be seen
+    pub fn release_all(&mut self) {
+        self.next_eject = None;
+        self.freeze_held = false;
+    }
+
+    /// Writes the pressed keys into `input`. Splits and ejects are left set
+    /// until a tick takes them with [`take_input`], so presses between ticks
+    /// aren't lost.
+    pub fn write_input(&mut self, input: &mut PlayerInput, now: f64) {
+        if self.split_pressed {
+            input.split = true;
+            self.split_pressed = false;
+        }
+        if let Some(next_eject) = self.next_eject {
+            if now >= next_eject {
+                input.eject = true;
+                // Ejects that were missed while the page was busy are skipped
+                // rather than all sent at once
+                let after = next_eject + Self::EJECT_REPEAT_INTERVAL;
+                self.next_eject = Some(if after <= now {
+                    now + Self::EJECT_REPEAT_INTERVAL
+                } else {
+                    after
+                });
+            }
+        }
+        input.freeze = self.freeze_held;
+    }
+}
+
+/// Returns the input for a tick, clearing the splits and ejects it includes
+/// so that each press only happens once
+pub fn take_input(input: &RefCell<PlayerInput>) -> PlayerInput {
+    let mut input = input.borrow_mut();
+    let tick_input = input.clone();
+    input.split = false;
+    input.eject = false;
+    tick_input
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Writes the keyboard's input at `now` and takes it for a tick
+    fn tick(keyboard: &mut KeyboardInput, input: &RefCell<PlayerInput>, now: f64) -> PlayerInput {
+        keyboard.write_input(&mut input.borrow_mut(), now);
+        take_input(input)
+    }
+
+    #[test]
+    fn bindings_can_be_overridden_from_the_query_string() {
+        let bindings = KeyBindings::from_query("?remote&split=KeyQ&unknown=KeyZ&eject=KeyE");
+        assert!(bindings.action("KeyQ") == Some(KeyAction::Split));
+        assert!(bindings.action("KeyE") == Some(KeyAction::Eject));
+        assert!(bindings.action("KeyS") == Some(KeyAction::Freeze));
+        assert!(bindings.action("Space").is_none());
+        assert!(bindings.action("KeyZ").is_none());
+    }
+
+    #[test]
+    fn only_bound_keys_are_handled() {
+        let mut keyboard = KeyboardInput::new(KeyBindings::default());
+        assert!(keyboard.key_down("Space", false, 0.0));
+        assert!(!keyboard.key_down("KeyA", false, 0.0));
+    }
+
+    #[test]
+    fn splits_happen_once_per_press() {
+        let mut keyboard = KeyboardInput::new(KeyBindings::default());
+        let input = RefCell::new(PlayerInput::default());
+        keyboard.key_down("Space", false, 0.0);
+        keyboard.key_down("Space", true, 10.0);
+        assert!(tick(&mut keyboard, &input, 20.0).split);
+        assert!(!tick(&mut keyboard, &input, 40.0).split);
+
+        keyboard.key_up("Space");
+        keyboard.key_down("Space", false, 50.0);
+        assert!(tick(&mut keyboard, &input, 60.0).split);
+    }
+
+    #[test]
+    fn presses_between_ticks_are_kept_until_a_tick_takes_them() {
+        let mut keyboard = KeyboardInput::new(KeyBindings::default());
+        let input = RefCell::new(PlayerInput::default());
+        keyboard.key_down("Space", false, 0.0);
+        keyboard.write_input(&mut input.borrow_mut(), 0.0);
+        keyboard.write_input(&mut input.borrow_mut(), 16.0);
+        assert!(take_input(&input).split);
+        assert!(!take_input(&input).split);
+    }
+
+    #[test]
+    fn ejects_repeat_while_the_key_is_held() {
+        let mut keyboard = KeyboardInput::new(KeyBindings::default());
+        let input = RefCell::new(PlayerInput::default());
+        keyboard.key_down("KeyW", false, 0.0);
+        assert!(tick(&mut keyboard, &input, 0.0).eject);
+        // The browser's key repeat doesn't eject any faster
+        keyboard.key_down("KeyW", true, 30.0);
+        assert!(!tick(&mut keyboard, &input, 40.0).eject);
+        assert!(tick(&mut keyboard, &input, KeyboardInput::EJECT_REPEAT_INTERVAL).eject);
+
+        keyboard.key_up("KeyW");
+        assert!(!tick(&mut keyboard, &input, 1000.0).eject);
+    }
+
+    #[test]
+    fn missed_ejects_are_skipped() {
+        let mut keyboard = KeyboardInput::new(KeyBindings::default());
+        let input = RefCell::new(PlayerInput::default());
+        keyboard.key_down("KeyW", false, 0.0);
+        assert!(tick(&mut keyboard, &input, 0.0).eject);
+        // Several intervals pass while the page is busy, but only one eject
+        // is sent for them
+        assert!(tick(&mut keyboard, &input, 1000.0).eject);
+        assert!(!tick(&mut keyboard, &input, 1001.0).eject);
+        assert!(
+            tick(
+                &mut keyboard,
+                &input,
+                1000.0 + KeyboardInput::EJECT_REPEAT_INTERVAL
+            )
+            .eject
+        );
+    }
+
+    #[test]
+    fn freezing_lasts_until_the_key_is_released() {
+        let mut keyboard = KeyboardInput::new(KeyBindings::default());
+        let input = RefCell::new(PlayerInput::default());
+        keyboard.key_down("KeyS", false, 0.0);
+        assert!(tick(&mut keyboard, &input, 0.0).freeze);
+        assert!(tick(&mut keyboard, &input, 100.0).freeze);
+        keyboard.key_up("KeyS");
+        assert!(!tick(&mut keyboard, &input, 200.0).freeze);
+    }
+
+    #[test]
+    fn losing_focus_releases_held_keys() {
+        let mut keyboard = KeyboardInput::new(KeyBindings::default());
+        let input = RefCell::new(PlayerInput::default());
+        keyboard.key_down("KeyS", false, 0.0);
+        keyboard.key_down("KeyW", false, 0.0);
+        keyboard.release_all();
+        let tick_input = tick(&mut keyboard, &input, 100.0);
+        assert!(!tick_input.freeze);
+        assert!(!tick_input.eject);
+    }
+}
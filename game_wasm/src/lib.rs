@@ -2,34 +2,71 @@ extern crate cfg_if;
 extern crate wasm_bindgen;
 extern crate web_sys;
 
+mod canvas_backend;
+mod keyboard_input;
 mod local_connection;
+mod prediction;
+mod remote_game;
 mod renderer;
 mod utils;
 mod view_history;
-mod view_scaler;
 mod view_snapshot;
 mod web_utils;
 
 use cell_game::{
+    bot::difficulty::Difficulty,
     client_connection::PlayerInput,
     game_view::GameView,
-    pos::{Point, Vec2},
-    server::game_server::GameServer,
+    pos::{Circle, Point},
+    server::{game_config::GameConfig, game_server::GameServer},
 };
+use game_render::animated_view::AnimatedView;
+use keyboard_input::{KeyBindings, KeyboardInput};
 use local_connection::LocalConnection;
+use prediction::Prediction;
 use renderer::CanvasRender;
 use std::{cell::RefCell, rc::Rc};
 use view_history::{BufferedView, ViewHistory};
 use wasm_bindgen::{prelude::*, JsCast};
 use web_utils::JsResult;
 
+/// How many bots play against the player in solo games
+const SOLO_BOTS: usize = 8;
+/// How many views the view history keeps
+const VIEW_BUFFER_SIZE: usize = 16;
+/// How many ticks in the past views are rendered. Local games only need to be
+/// a tick behind to have a view to interpolate towards, but views from a
+/// server arrive unevenly and need more slack. The player's own cells in
+/// remote games are drawn where they're predicted to be now, so the delay only
+/// applies to everything else.
+const SOLO_RENDER_DELAY_TICKS: f64 = 1.0;
+const REMOTE_RENDER_DELAY_TICKS: f64 = 3.0;
+
 #[wasm_bindgen(start)]
 pub fn start() -> JsResult {
-    let mut game = GameServer::new();
+    utils::set_panic_hook();
+
+    // Pages opened with `?solo` run their own game instead of joining the
+    // server they were loaded from
+    let location = web_utils::window().location();
+    let search = location.search()?;
+    let solo = search.contains("solo");
+    // Pages opened with `?debug` show how full the view buffer is
+    let debug = search.contains("debug");
+    let tick_rate = GameConfig::default().tick_rate;
+    let tick_duration = 1_000.0 / tick_rate as f64;
 
-    // The view history keeps copies of the view of previous ticks of the game.
-    // This is kept for rendering until the next tick.
-    let view_history_reader = Rc::new(RefCell::new(ViewHistory::new()));
+    // The view history keeps copies of the views of recent ticks of the game,
+    // which are rendered a short delay behind when they arrived.
+    let render_delay_ticks = if solo {
+        SOLO_RENDER_DELAY_TICKS
+    } else {
+        REMOTE_RENDER_DELAY_TICKS
+    };
+    let view_history_reader = Rc::new(RefCell::new(ViewHistory::new(
+        VIEW_BUFFER_SIZE,
+        render_delay_ticks * tick_duration,
+    )));
     let view_history_writer = view_history_reader.clone();
     // Keeps track of where the mouse was most recently moved to.
     // When rendering, this is mapped to a game position that is stored in
@@ -38,18 +75,52 @@ pub fn start() -> JsResult {
     let canvas_move_writer = canvas_move_reader.clone();
     // Keeps track of the most recent input made while rendering, and read each
     // game tick.
-    let player_input_reader = Rc::new(RefCell::new(PlayerInput {
-        move_vec: Vec2::ZERO,
-    }));
+    let player_input_reader = Rc::new(RefCell::new(PlayerInput::default()));
     let player_input_writer = player_input_reader.clone();
-    // Keeps track of when the last tick was run. This is used for visually
-    // interpolating between ticks when the refresh rate of the renderer is
-    // faster than the game's tick rate.
-    let tick_timestamp_reader = Rc::new(RefCell::new(None));
-    let tick_timestamp_writer = tick_timestamp_reader.clone();
+    // Keeps track of which bound keys are held. When rendering, these are
+    // written to player_input_writer along with the mouse position.
+    let keyboard_reader = Rc::new(RefCell::new(KeyboardInput::new(KeyBindings::from_query(
+        &search,
+    ))));
 
-    let conn = LocalConnection::new(player_input_reader, view_history_writer);
-    game.connect_player("Player".to_owned(), Box::new(conn));
+    // Remote games predict where the player's own cells are ahead of the
+    // server. Local games have no lag to hide, so there is nothing to predict.
+    let prediction_reader = if solo {
+        let mut game = GameServer::new();
+        let conn = LocalConnection::new(player_input_reader, view_history_writer);
+        game.connect_player("Player".to_owned(), Box::new(conn));
+        for i in 0..SOLO_BOTS {
+            game.connect_bot(format!("Bot {}", i + 1), Difficulty::Normal);
+        }
+        web_utils::set_interval(
+            Box::leak(Box::new(Closure::new(move || {
+                game.tick();
+                // Everyone respawns straight away in solo games
+                game.respawn_dead_players();
+            }))),
+            tick_duration as i32,
+        );
+        None
+    } else {
+        let scheme = if location.protocol()? == "https:" {
+            "wss"
+        } else {
+            "ws"
+        };
+        // Servers don't send their config, so this assumes they move cells at
+        // the default speeds
+        let prediction_reader = Rc::new(RefCell::new(Prediction::new(
+            GameConfig::default().speed_curve,
+        )));
+        remote_game::connect(
+            &format!("{}://{}/ws", scheme, location.host()?),
+            "Player".to_owned(),
+            player_input_reader,
+            view_history_writer,
+            prediction_reader.clone(),
+        )?;
+        Some(prediction_reader)
+    };
 
     let mouse_move_callback_ref: Box<Closure<dyn FnMut(web_sys::MouseEvent)>> =
         Box::new(Closure::new(move |e: web_sys::MouseEvent| {
@@ -75,27 +146,68 @@ pub fn start() -> JsResult {
         )
         .expect("can not set listener mousemove on canvas");
 
+    let key_down_writer = keyboard_reader.clone();
+    let key_down_callback_ref: Box<Closure<dyn FnMut(web_sys::KeyboardEvent)>> =
+        Box::new(Closure::new(move |e: web_sys::KeyboardEvent| {
+            if key_down_writer
+                .borrow_mut()
+                .key_down(&e.code(), e.repeat(), web_utils::now())
+            {
+                // Stops space from scrolling the page
+                e.prevent_default();
+            }
+        }));
+    web_utils::window()
+        .add_event_listener_with_callback(
+            "keydown",
+            Box::leak(key_down_callback_ref).as_ref().unchecked_ref(),
+        )
+        .expect("can not set listener keydown on window");
+
+    let key_up_writer = keyboard_reader.clone();
+    let key_up_callback_ref: Box<Closure<dyn FnMut(web_sys::KeyboardEvent)>> =
+        Box::new(Closure::new(move |e: web_sys::KeyboardEvent| {
+            key_up_writer.borrow_mut().key_up(&e.code());
+        }));
+    web_utils::window()
+        .add_event_listener_with_callback(
+            "keyup",
+            Box::leak(key_up_callback_ref).as_ref().unchecked_ref(),
+        )
+        .expect("can not set listener keyup on window");
+
+    // Key releases aren't seen while the page is out of focus, so held keys
+    // would otherwise stay held
+    let blur_writer = keyboard_reader.clone();
+    let blur_callback_ref: Box<Closure<dyn FnMut()>> = Box::new(Closure::new(move || {
+        blur_writer.borrow_mut().release_all();
+    }));
+    web_utils::window()
+        .add_event_listener_with_callback(
+            "blur",
+            Box::leak(blur_callback_ref).as_ref().unchecked_ref(),
+        )
+        .expect("can not set listener blur on window");
+
     let mut renderer = CanvasRender::new();
     let render_callback_ref_outer = Rc::new(RefCell::new(None));
     let render_callback_ref_inner = render_callback_ref_outer.clone();
 
     let render_callback = Closure::new(move |timestamp| {
-        let delta = tick_timestamp_reader
-            .borrow()
-            .map(|last_tick| (timestamp - last_tick) / (1_000.0 / GameServer::TICK_RATE as f64))
-            .unwrap_or(0.0);
-
-        let view_area = match view_history_reader.borrow().get_interpolated_view(delta) {
+        let view_history = view_history_reader.borrow();
+        let prediction = prediction_reader.as_ref().map(|reader| reader.borrow());
+        let view_area = match view_history.get_interpolated_view(timestamp) {
             Some(BufferedView::Interpolated(view)) => {
-                renderer.render(&view);
-                Some(view.view_area())
+                Some(render_view(&mut renderer, &view, prediction.as_deref()))
             }
             Some(BufferedView::Snapshot(view)) => {
-                renderer.render(view);
-                Some(view.view_area())
+                Some(render_view(&mut renderer, view, prediction.as_deref()))
             }
             None => None,
         };
+        if debug {
+            renderer.render_buffer_health(&view_history.health(timestamp));
+        }
 
         if let Some(move_vec) = renderer
             .view_scaler()
@@ -105,20 +217,37 @@ pub fn start() -> JsResult {
                 circle.center.vec_to(scaler.canvas_to_game_pos(canvas_pos))
             })
         {
-            *player_input_writer.borrow_mut() = PlayerInput { move_vec };
+            player_input_writer.borrow_mut().move_vec = move_vec;
         }
+        keyboard_reader
+            .borrow_mut()
+            .write_input(&mut player_input_writer.borrow_mut(), timestamp);
 
         web_utils::request_animation_frame(render_callback_ref_inner.borrow().as_ref().unwrap());
     });
     *render_callback_ref_outer.borrow_mut() = Some(render_callback);
     web_utils::request_animation_frame(render_callback_ref_outer.borrow().as_ref().unwrap());
-    web_utils::set_interval(
-        Box::leak(Box::new(Closure::new(move || {
-            game.tick();
-            *tick_timestamp_writer.borrow_mut() = Some(web_utils::now());
-        }))),
-        1_000 / GameServer::TICK_RATE as i32,
-    );
 
     Ok(())
 }
+
+/// Renders a view from the view history, with the player's own cells drawn
+/// where they're predicted to be if there is a prediction. Returns the view
+/// area that was rendered.
+fn render_view(
+    renderer: &mut CanvasRender,
+    view: &impl AnimatedView,
+    prediction: Option<&Prediction>,
+) -> Circle {
+    match prediction {
+        Some(prediction) => {
+            let view = prediction.predicted_view(view);
+            renderer.render(&view);
+            view.view_area()
+        }
+        None => {
+            renderer.render(view);
+            view.view_area()
+        }
+    }
+}
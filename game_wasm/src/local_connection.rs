@@ -5,7 +5,7 @@ use cell_game::{
     server::server_view::ServerView,
 };
 
-use crate::view_history::ViewHistory;
+use crate::{keyboard_input, view_history::ViewHistory, web_utils};
 
 pub struct LocalConnection {
     player_move_reader: Rc<RefCell<PlayerInput>>,
@@ -16,8 +16,10 @@ impl<'a> ClientConnection<'a> for LocalConnection {
     type V = ServerView<'a>;
 
     fn on_tick(&mut self, view: Self::V) -> PlayerInput {
-        self.view_history_writer.borrow_mut().update(&view);
-        self.player_move_reader.borrow_mut().clone()
+        self.view_history_writer
+            .borrow_mut()
+            .update(&view, web_utils::now());
+        keyboard_input::take_input(&self.player_move_reader)
     }
 }
 
@@ -0,0 +1,236 @@
+use std::collections::{HashMap, VecDeque};
+
+use cell_game::{
+    cells::{
+        cell::Cell, ejected_mass_cell::EjectedMassCell, food_cell::FoodCell,
+        player_cell::PlayerCell, virus_cell::VirusCell,
+    },
+    client_connection::PlayerInput,
+    game_view::GameView,
+    ids::{PlayerCellId, PlayerId},
+    leaderboard::LeaderboardEntry,
+    player_info::PlayerInfo,
+    pos::{Circle, Point, Rect, Vec2},
+    server::game_config::SpeedCurve,
+};
+use game_render::animated_view::{AnimatedView, FadingCell};
+
+/// Predicts where the player's own cells are by replaying the inputs the
+/// server hasn't applied yet on top of the last view it sent. Only movement is
+/// predicted. Splits, collisions and everything else are left to the server,
+/// and any difference is corrected over a few ticks instead of all at once.
+pub struct Prediction {
+    speed_curve: SpeedCurve,
+    world_bounds: Option<Rect>,
+    /// The sequence number the next input will be sent with
+    next_seq: u32,
+    /// Inputs that have been sent but aren't included in a view yet, oldest
+    /// first
+    unacked: VecDeque<(u32, PlayerInput)>,
+    /// The player's cells after replaying every unacknowledged input
+    predicted: Vec<PlayerCell>,
+    /// How far each cell is drawn from its predicted position
+    corrections: HashMap<PlayerCellId, Vec2>,
+}
+
+/// A view with the owner's cells drawn where they are predicted to be now.
+/// Everything else is taken from the wrapped view, which can be an older view
+/// from the view history.
+pub struct PredictedView<'a, V: GameView> {
+    view: &'a V,
+    prediction: &'a Prediction,
+}
+
+impl Prediction {
+    /// The fraction of a correction that is left after each tick
+    const CORRECTION_DECAY: f64 = 0.8;
+    /// Cells that are further than this from where they were predicted to be
+    /// have probably been launched or eaten, so they aren't smoothed
+    const SNAP_DISTANCE: f64 = 100.0;
+
+    /// `speed_curve` must match the server's, or every prediction will be off
+    pub fn new(speed_curve: SpeedCurve) -> Self {
+        Self {
+            speed_curve,
+            world_bounds: None,
+            next_seq: 1,
+            unacked: VecDeque::new(),
+            predicted: Vec::new(),
+            corrections: HashMap::new(),
+        }
+    }
+
+    /// Starts predicting again from a view sent by the server. `input_ack` is
+    /// the last input the server applied before the view.
+    pub fn reconcile(&mut self, view: &impl GameView, input_ack: u32) {
+        self.world_bounds = Some(view.world_bounds());
+        while self
+            .unacked
+            .front()
+            .is_some_and(|(seq, _)| *seq <= input_ack)
+        {
+            self.unacked.pop_front();
+        }
+
+        let mut predicted: Vec<PlayerCell> = view
+            .player_cells()
+            .filter(|cell| cell.owner() == view.owner())
+            .collect();
+        for (_, input) in self.unacked.iter() {
+            self.apply(&mut predicted, input);
+        }
+
+        // Wherever the old prediction disagrees, the cells keep being drawn
+        // where they were and drift to the new prediction from there
+        let drawn: HashMap<PlayerCellId, Point> = self
+            .predicted
+            .iter()
+            .map(|cell| (cell.id(), self.drawn_pos(cell)))
+            .collect();
+        self.corrections = predicted
+            .iter()
+            .filter_map(|cell| {
+                let error = cell.pos().vec_to(*drawn.get(&cell.id())?);
+                (error.magnitude() < Self::SNAP_DISTANCE)
+                    .then(|| (cell.id(), error * Self::CORRECTION_DECAY))
+            })
+            .collect();
+        self.predicted = predicted;
+    }
+
+    /// Predicts the effect of an input that is about to be sent, returning
+    /// the sequence number to send it with
+    pub fn push_input(&mut self, input: PlayerInput) -> u32 {
+        let seq = self.next_seq;
+        self.next_seq += 1;
+        let mut predicted = std::mem::take(&mut self.predicted);
+        self.apply(&mut predicted, &input);
+        self.predicted = predicted;
+        self.unacked.push_back((seq, input));
+        seq
+    }
+
+    pub fn predicted_view<'a, V: GameView>(&'a self, view: &'a V) -> PredictedView<'a, V> {
+        PredictedView {
+            view,
+            prediction: self,
+        }
+    }
+
+    /// Moves the cells the same way the server does when it applies an input
+    fn apply(&self, cells: &mut [PlayerCell], input: &PlayerInput) {
+        let (Some(bounds), Some(center)) = (self.world_bounds, center_of_mass(cells)) else {
+            return;
+        };
+        if !input.freeze {
+            let move_to = center.offset(input.move_vec);
+            for cell in cells.iter_mut() {
+                cell.move_player(move_to, bounds, &self.speed_curve);
+            }
+        }
+        for cell in cells.iter_mut() {
+            cell.apply_velocity(bounds);
+        }
+    }
+
+    fn drawn_pos(&self, cell: &PlayerCell) -> Point {
+        match self.corrections.get(&cell.id()) {
+            Some(correction) => cell.pos().offset(*correction),
+            None => cell.pos(),
+        }
+    }
+
+    /// The player's cells where they should be drawn
+    fn drawn_cells(&self) -> impl Iterator<Item = PlayerCell> + '_ {
+        self.predicted.iter().map(|cell| {
+            let mut cell = cell.clone();
+            *cell.pos_mut() = self.drawn_pos(&cell);
+            cell
+        })
+    }
+}
+
+impl<V: GameView> GameView for PredictedView<'_, V> {
+    fn player_cells(&self) -> impl Iterator<Item = PlayerCell> {
+        let owner = self.view.owner();
+        self.view
+            .player_cells()
+            .filter(move |cell| cell.owner() != owner)
+            .chain(self.prediction.drawn_cells())
+    }
+
+    fn food_cells(&self) -> impl Iterator<Item = FoodCell> {
+        self.view.food_cells()
+    }
+
+    fn ejected_mass_cells(&self) -> impl Iterator<Item = EjectedMassCell> {
+        self.view.ejected_mass_cells()
+    }
+
+    fn virus_cells(&self) -> impl Iterator<Item = VirusCell> {
+        self.view.virus_cells()
+    }
+
+    fn player_infos(&self) -> impl Iterator<Item = &PlayerInfo> {
+        self.view.player_infos()
+    }
+
+    /// The server centers the view area on the player's cells, so it's
+    /// centered on where they're drawn instead
+    fn view_area(&self) -> Circle {
+        let view_area = self.view.view_area();
+        let drawn: Vec<PlayerCell> = self.prediction.drawn_cells().collect();
+        match center_of_mass(&drawn) {
+            Some(center) => Circle {
+                center,
+                radius: view_area.radius,
+            },
+            None => view_area,
+        }
+    }
+
+    fn world_bounds(&self) -> Rect {
+        self.view.world_bounds()
+    }
+
+    fn owner(&self) -> PlayerId {
+        self.view.owner()
+    }
+
+    fn leaderboard(&self) -> impl Iterator<Item = LeaderboardEntry> {
+        self.view.leaderboard()
+    }
+}
+
+impl<V: AnimatedView> AnimatedView for PredictedView<'_, V> {
+    /// The owner's cells are always drawn where they're predicted to be, so
+    /// only other players' cells fade
+    fn fading_player_cells(&self) -> impl Iterator<Item = FadingCell<PlayerCell>> {
+        let owner = self.view.owner();
+        self.view
+            .fading_player_cells()
+            .filter(move |fading| fading.cell.owner() != owner)
+    }
+
+    fn fading_food_cells(&self) -> impl Iterator<Item = FadingCell<FoodCell>> {
+        self.view.fading_food_cells()
+    }
+}
+
+/// Where the server centers a player's view area, or `None` if they have no
+/// cells
+fn center_of_mass(cells: &[PlayerCell]) -> Option<Point> {
+    let total_mass: f64 = cells.iter().map(|cell| cell.mass()).sum();
+    (total_mass > 0.0).then(|| {
+        let (x, y) = cells.iter().fold((0.0, 0.0), |(x, y), cell| {
+            (
+                x + cell.pos().x * cell.mass(),
+                y + cell.pos().y * cell.mass(),
+            )
+        });
+        Point {
+            x: x / total_mass,
+            y: y / total_mass,
+        }
+    })
+}
@@ -0,0 +1,97 @@
+use std::{cell::RefCell, rc::Rc};
+
+use cell_game::{
+    client_connection::PlayerInput,
+    protocol::{
+        delta::ViewDecoder,
+        messages::{ClientMessage, ServerMessage},
+        PROTOCOL_VERSION,
+    },
+};
+use wasm_bindgen::{prelude::*, JsCast};
+use web_sys::{BinaryType, MessageEvent, WebSocket};
+
+use crate::{
+    keyboard_input,
+    prediction::Prediction,
+    view_history::ViewHistory,
+    web_utils::{self, JsResult},
+};
+
+/// Joins the game hosted at `url`. Views received from the server are rebuilt
+/// from their deltas and stored in the view history, and the player's most
+/// recent input is sent back every time a view arrives. Each view and input
+/// also updates `prediction_writer`, so that the player's own cells can be
+/// drawn where they will be once the server has applied every input sent so
+/// far.
+pub fn connect(
+    url: &str,
+    name: String,
+    player_input_reader: Rc<RefCell<PlayerInput>>,
+    view_history_writer: Rc<RefCell<ViewHistory>>,
+    prediction_writer: Rc<RefCell<Prediction>>,
+) -> JsResult {
+    let socket = WebSocket::new(url)?;
+    socket.set_binary_type(BinaryType::Arraybuffer);
+
+    let open_socket = socket.clone();
+    let open_callback: Closure<dyn FnMut()> = Closure::new(move || {
+        let hello = ClientMessage::Hello {
+            version: PROTOCOL_VERSION,
+            name: name.clone(),
+        };
+        send(&open_socket, hello);
+    });
+    socket.set_onopen(Some(
+        Box::leak(Box::new(open_callback)).as_ref().unchecked_ref(),
+    ));
+
+    let message_socket = socket.clone();
+    let mut decoder = ViewDecoder::new();
+    let message_callback: Closure<dyn FnMut(MessageEvent)> =
+        Closure::new(move |e: MessageEvent| {
+            let Ok(buffer) = e.data().dyn_into::<js_sys::ArrayBuffer>() else {
+                return;
+            };
+            let frame = js_sys::Uint8Array::new(&buffer).to_vec();
+            match ServerMessage::decode(&frame) {
+                Ok(ServerMessage::View(delta)) => {
+                    let frame = delta.frame;
+                    let input_ack = delta.input_ack;
+                    let input = keyboard_input::take_input(&player_input_reader);
+                    match decoder.decode(*delta) {
+                        Ok(view) => {
+                            let mut prediction = prediction_writer.borrow_mut();
+                            prediction.reconcile(&view, input_ack);
+                            let seq = prediction.push_input(input.clone());
+                            view_history_writer
+                                .borrow_mut()
+                                .update(&view, web_utils::now());
+                            send(&message_socket, ClientMessage::Ack(frame));
+                            send(&message_socket, ClientMessage::Input { seq, input });
+                        }
+                        Err(e) => web_utils::log_error(&format!("could not apply view: {}", e)),
+                    }
+                }
+                Ok(ServerMessage::Welcome { .. }) => {}
+                Ok(ServerMessage::VersionMismatch { version }) => web_utils::log_error(&format!(
+                    "server speaks protocol version {} but this client speaks {}",
+                    version, PROTOCOL_VERSION
+                )),
+                Err(e) => web_utils::log_error(&format!("could not decode message: {}", e)),
+            }
+        });
+    socket.set_onmessage(Some(
+        Box::leak(Box::new(message_callback))
+            .as_ref()
+            .unchecked_ref(),
+    ));
+
+    Ok(())
+}
+
+fn send(socket: &WebSocket, message: ClientMessage) {
+    if let Err(e) = socket.send_with_u8_array(&message.encode()) {
+        web_sys::console::error_1(&e);
+    }
+}
@@ -1,18 +1,30 @@
+use std::collections::VecDeque;
+
 use cell_game::{
-    cells::{cell::Cell, food_cell::FoodCell, player_cell::PlayerCell},
+    cells::{
+        cell::Cell, ejected_mass_cell::EjectedMassCell, food_cell::FoodCell,
+        player_cell::PlayerCell, virus_cell::VirusCell,
+    },
     game_view::GameView,
     ids::PlayerId,
+    leaderboard::LeaderboardEntry,
     player_info::PlayerInfo,
-    pos::{Circle, Point},
+    pos::{Circle, Point, Rect},
 };
 
+use game_render::animated_view::{AnimatedView, FadingCell};
+
 use crate::view_snapshot::ViewSnapshot;
 
-/// Keeps track of previous view states so that they can be interpolated
-/// between when rendering.
+/// Buffers the most recent views along with when they arrived. Views are
+/// rendered a fixed delay in the past, so that there is usually a newer view
+/// to interpolate towards even when views arrive unevenly.
 pub struct ViewHistory {
-    curr: Option<ViewSnapshot>,
-    prev: Option<ViewSnapshot>,
+    /// Snapshots and the times they arrived in milliseconds, oldest first
+    snapshots: VecDeque<(f64, ViewSnapshot)>,
+    capacity: usize,
+    /// How many milliseconds in the past views are rendered
+    render_delay: f64,
 }
 
 pub struct InterpolatedView<'a> {
@@ -26,65 +38,244 @@ pub enum BufferedView<'a> {
     Interpolated(InterpolatedView<'a>),
 }
 
+/// How well the buffer is keeping up with rendering, for debugging
+pub struct BufferHealth {
+    /// How many snapshots are buffered
+    pub snapshots: usize,
+    pub capacity: usize,
+    /// How many milliseconds the newest snapshot is ahead of the render time.
+    /// This is negative when the buffer has run dry.
+    pub lead: f64,
+    /// Whether views are being extrapolated past the newest snapshot
+    pub extrapolating: bool,
+}
+
 impl ViewHistory {
-    pub fn new() -> Self {
+    /// How far past the newest snapshot views can be extrapolated, as a
+    /// fraction of the time between the two newest snapshots. Cells stop
+    /// moving after this instead of overshooting further.
+    const MAX_EXTRAPOLATION: f64 = 0.5;
+
+    /// Creates a buffer that holds up to `capacity` snapshots, and renders
+    /// `render_delay` milliseconds in the past
+    pub fn new(capacity: usize, render_delay: f64) -> Self {
         Self {
-            curr: None,
-            prev: None,
+            snapshots: VecDeque::with_capacity(capacity),
+            capacity: capacity.max(2),
+            render_delay,
         }
     }
 
-    pub fn update<'a, V: GameView>(&mut self, view: &'a V) {
-        self.prev = self.curr.take();
-        self.curr = Some(ViewSnapshot::new(view));
+    /// Stores a view that arrived at `timestamp`
+    pub fn update<V: GameView>(&mut self, view: &V, timestamp: f64) {
+        if self.snapshots.len() == self.capacity {
+            self.snapshots.pop_front();
+        }
+        self.snapshots
+            .push_back((timestamp, ViewSnapshot::new(view)));
     }
 
-    pub fn get_interpolated_view(&self, delta: f64) -> Option<BufferedView> {
-        if let (Some(curr), Some(prev)) = (self.curr.as_ref(), self.prev.as_ref()) {
-            Some(BufferedView::Interpolated(InterpolatedView {
-                curr,
-                prev,
-                delta,
-            }))
+    /// The view to render at `now`, interpolated between the two snapshots
+    /// around the render time
+    pub fn get_interpolated_view(&self, now: f64) -> Option<BufferedView<'_>> {
+        let render_time = now - self.render_delay;
+        let (first_time, first) = self.snapshots.front()?;
+        if self.snapshots.len() == 1 || render_time <= *first_time {
+            return Some(BufferedView::Snapshot(first));
+        }
+
+        let next = self
+            .snapshots
+            .iter()
+            .position(|(timestamp, _)| *timestamp > render_time);
+        let (prev_time, prev, curr_time, curr) = match next {
+            Some(i) => {
+                let (prev_time, prev) = &self.snapshots[i - 1];
+                let (curr_time, curr) = &self.snapshots[i];
+                (*prev_time, prev, *curr_time, curr)
+            }
+            // The buffer has run dry, so the last two snapshots are
+            // extrapolated from instead
+            None => {
+                let (prev_time, prev) = &self.snapshots[self.snapshots.len() - 2];
+                let (curr_time, curr) = &self.snapshots[self.snapshots.len() - 1];
+                (*prev_time, prev, *curr_time, curr)
+            }
+        };
+        let interval = curr_time - prev_time;
+        let delta = if interval > 0.0 {
+            ((render_time - prev_time) / interval).min(1.0 + Self::MAX_EXTRAPOLATION)
         } else {
-            self.curr.as_ref().map(BufferedView::Snapshot)
+            1.0
+        };
+        Some(BufferedView::Interpolated(InterpolatedView {
+            curr,
+            prev,
+            delta,
+        }))
+    }
+
+    pub fn health(&self, now: f64) -> BufferHealth {
+        let lead = self
+            .snapshots
+            .back()
+            .map_or(0.0, |(timestamp, _)| timestamp - (now - self.render_delay));
+        BufferHealth {
+            snapshots: self.snapshots.len(),
+            capacity: self.capacity,
+            lead,
+            extrapolating: self.snapshots.len() > 1 && lead < 0.0,
         }
     }
 }
 
 impl GameView for InterpolatedView<'_> {
+    /// Cells in both snapshots, moved and resized part of the way between
+    /// them
     fn player_cells(&self) -> impl Iterator<Item = PlayerCell> {
-        self.curr.player_cells().map(|mut cell| {
-            if let Some(prev_cell) = self
-                .prev
-                .player_cells()
-                .find(|prev_cell| prev_cell.id() == cell.id())
-            {
-                *cell.pos_mut() = Self::lerp_point(prev_cell.pos(), cell.pos(), self.delta);
-                *cell.mass_mut() = Self::lerp_f64(prev_cell.mass(), cell.mass(), self.delta);
-            }
+        self.curr
+            .player_cells()
+            .filter_map(|cell| self.interpolated(&cell))
+    }
+
+    /// Food never moves, so there is nothing to interpolate
+    fn food_cells(&self) -> impl Iterator<Item = FoodCell> {
+        self.curr
+            .food_cells()
+            .filter(|cell| self.prev.food_cell(cell.id()).is_some())
+    }
+
+    /// Ejected mass has no ids to match cells between snapshots with, so it
+    /// is extrapolated from the previous snapshot using its velocity instead.
+    fn ejected_mass_cells(&self) -> impl Iterator<Item = EjectedMassCell> {
+        self.prev.ejected_mass_cells().map(|mut cell| {
+            *cell.pos_mut() = cell.pos().offset(cell.velocity() * self.delta);
             cell
         })
     }
 
-    fn food_cells(&self) -> impl Iterator<Item = FoodCell> {
-        self.prev.food_cells()
+    /// Viruses are extrapolated the same way as ejected mass
+    fn virus_cells(&self) -> impl Iterator<Item = VirusCell> {
+        self.prev.virus_cells().map(|mut cell| {
+            *cell.pos_mut() = cell.pos().offset(cell.velocity() * self.delta);
+            cell
+        })
     }
 
     fn player_infos(&self) -> impl Iterator<Item = &PlayerInfo> {
         self.curr.player_infos()
     }
 
+    /// The center is extrapolated along with the cells, but like their mass
+    /// the radius stops at its newest size
     fn view_area(&self) -> Circle {
-        Self::lerp_circle(self.prev.view_area(), self.curr.view_area(), self.delta)
+        let (prev, curr) = (self.prev.view_area(), self.curr.view_area());
+        Circle {
+            center: Self::lerp_point(prev.center, curr.center, self.delta),
+            radius: Self::lerp_f64(prev.radius, curr.radius, self.fade_in()),
+        }
+    }
+
+    fn world_bounds(&self) -> Rect {
+        self.curr.world_bounds()
     }
 
     fn owner(&self) -> PlayerId {
         self.curr.owner()
     }
+
+    /// Ranks only change on ticks, so the leaderboard is taken from the
+    /// current snapshot
+    fn leaderboard(&self) -> impl Iterator<Item = LeaderboardEntry> {
+        self.curr.leaderboard()
+    }
+}
+
+impl AnimatedView for InterpolatedView<'_> {
+    /// New cells grow in where they are. Cells that were eaten shrink into
+    /// the cell that ate them, and other cells that left just shrink away.
+    fn fading_player_cells(&self) -> impl Iterator<Item = FadingCell<PlayerCell>> {
+        let entering = self
+            .curr
+            .player_cells()
+            .filter(|cell| self.prev.player_cell(cell.id()).is_none())
+            .map(|cell| FadingCell {
+                cell,
+                fade: self.fade_in(),
+            });
+        let leaving = self
+            .prev
+            .player_cells()
+            .filter(|cell| self.curr.player_cell(cell.id()).is_none())
+            .map(|mut cell| {
+                if let Some(eater) = self.eater(cell.pos(), cell.mass()) {
+                    *cell.pos_mut() = Self::lerp_point(cell.pos(), eater, self.fade_in());
+                }
+                FadingCell {
+                    cell,
+                    fade: 1.0 - self.fade_in(),
+                }
+            });
+        entering.chain(leaving)
+    }
+
+    /// New food grows in, and eaten food shrinks into the cell that ate it
+    fn fading_food_cells(&self) -> impl Iterator<Item = FadingCell<FoodCell>> {
+        let entering = self
+            .curr
+            .food_cells()
+            .filter(|cell| self.prev.food_cell(cell.id()).is_none())
+            .map(|cell| FadingCell {
+                cell,
+                fade: self.fade_in(),
+            });
+        let leaving = self
+            .prev
+            .food_cells()
+            .filter(|cell| self.curr.food_cell(cell.id()).is_none())
+            .map(|mut cell| {
+                if let Some(eater) = self.eater(cell.pos(), cell.mass()) {
+                    *cell.pos_mut() = Self::lerp_point(cell.pos(), eater, self.fade_in());
+                }
+                FadingCell {
+                    cell,
+                    fade: 1.0 - self.fade_in(),
+                }
+            });
+        entering.chain(leaving)
+    }
 }
 
 impl InterpolatedView<'_> {
+    /// How far through appearing cells are. Unlike `delta` this never goes
+    /// past 1 when extrapolating.
+    fn fade_in(&self) -> f64 {
+        self.delta.clamp(0.0, 1.0)
+    }
+
+    /// A cell from the current snapshot part of the way to where it is from
+    /// where it was, or `None` if it wasn't in the previous snapshot
+    fn interpolated(&self, cell: &PlayerCell) -> Option<PlayerCell> {
+        let prev_cell = self.prev.player_cell(cell.id())?;
+        let mut cell = cell.clone();
+        *cell.pos_mut() = Self::lerp_point(prev_cell.pos(), cell.pos(), self.delta);
+        // Mass isn't extrapolated, so that a cell that just split or popped
+        // doesn't shrink past its new size into a negative mass
+        *cell.mass_mut() = Self::lerp_f64(prev_cell.mass(), cell.mass(), self.fade_in());
+        Some(cell)
+    }
+
+    /// Where the player cell that ate something at `pos` is being drawn, if
+    /// one can be found. The eater must have been more massive than what it
+    /// ate.
+    fn eater(&self, pos: Point, mass: f64) -> Option<Point> {
+        self.curr
+            .player_cells()
+            .filter(|cell| cell.mass() > mass && cell.hitbox().contains_point(pos))
+            .max_by(|a, b| a.mass().total_cmp(&b.mass()))
+            .map(|cell| self.interpolated(&cell).unwrap_or(cell).pos())
+    }
+
     fn lerp_f64(prev: f64, curr: f64, delta: f64) -> f64 {
         prev + (curr - prev) * delta
     }
@@ -95,11 +286,40 @@ impl InterpolatedView<'_> {
             y: Self::lerp_f64(prev.y, curr.y, delta),
         }
     }
+}
 
-    fn lerp_circle(prev: Circle, curr: Circle, delta: f64) -> Circle {
-        Circle {
-            center: Self::lerp_point(prev.center, curr.center, delta),
-            radius: Self::lerp_f64(prev.radius, curr.radius, delta),
-        }
+#[cfg(test)]
+mod tests {
+    use cell_game::{
+        bot::difficulty::Difficulty, protocol::messages::ViewFrame,
+        server::game_server::GameServer, test_support::small_config,
+    };
+
+    use super::*;
+
+    #[test]
+    fn shrinking_cells_are_not_extrapolated_past_their_newest_size() {
+        let mut game = GameServer::with_seed(small_config(), 1);
+        let player = game.connect_bot("Bot".into(), Difficulty::Easy);
+        game.tick();
+        let mut prev = ViewFrame::new(&game.player_view(player).unwrap());
+        *prev.player_cells[0].mass_mut() = 400.0;
+        prev.view_area.radius = 300.0;
+        let mut curr = prev.clone();
+        *curr.player_cells[0].mass_mut() = 50.0;
+        curr.view_area.radius = 100.0;
+
+        let mut history = ViewHistory::new(4, 0.0);
+        history.update(&prev, 0.0);
+        history.update(&curr, 100.0);
+        let Some(BufferedView::Interpolated(view)) = history.get_interpolated_view(150.0) else {
+            panic!("expected an interpolated view");
+        };
+        assert!(view.delta > 1.0, "the view isn't extrapolated");
+
+        let cell = view.player_cells().next().unwrap();
+        assert_eq!(cell.mass(), 50.0);
+        assert!(cell.radius() > 0.0);
+        assert_eq!(view.view_area().radius, 100.0);
     }
 }
@@ -1,32 +1,75 @@
+use std::collections::HashMap;
+
 use cell_game::{
-    cells::{food_cell::FoodCell, player_cell::PlayerCell},
+    cells::{
+        ejected_mass_cell::EjectedMassCell, food_cell::FoodCell, player_cell::PlayerCell,
+        virus_cell::VirusCell,
+    },
     game_view::GameView,
-    ids::PlayerId,
+    ids::{FoodCellId, PlayerCellId, PlayerId},
+    leaderboard::LeaderboardEntry,
     player_info::PlayerInfo,
-    pos::Circle,
+    pos::{Circle, Rect},
 };
 
+use game_render::animated_view::AnimatedView;
+
 /// Stores a view's current state so that it can be used later
 pub struct ViewSnapshot {
     players: Vec<PlayerCell>,
     food: Vec<FoodCell>,
+    ejected_mass: Vec<EjectedMassCell>,
+    viruses: Vec<VirusCell>,
     info: Vec<PlayerInfo>,
+    leaderboard: Vec<LeaderboardEntry>,
     view_area: Circle,
+    world_bounds: Rect,
     owner: PlayerId,
+    /// The index of each player cell in `players`
+    player_indices: HashMap<PlayerCellId, usize>,
+    /// The index of each food cell in `food`
+    food_indices: HashMap<FoodCellId, usize>,
 }
 
 impl ViewSnapshot {
     pub fn new(view: &impl GameView) -> Self {
+        let players: Vec<PlayerCell> = view.player_cells().collect();
+        let food: Vec<FoodCell> = view.food_cells().collect();
         Self {
-            players: view.player_cells().collect(),
-            food: view.food_cells().collect(),
+            player_indices: players
+                .iter()
+                .enumerate()
+                .map(|(i, cell)| (cell.id(), i))
+                .collect(),
+            food_indices: food
+                .iter()
+                .enumerate()
+                .map(|(i, cell)| (cell.id(), i))
+                .collect(),
+            players,
+            food,
+            ejected_mass: view.ejected_mass_cells().collect(),
+            viruses: view.virus_cells().collect(),
             info: view.player_infos().cloned().collect(),
+            leaderboard: view.leaderboard().collect(),
             view_area: view.view_area(),
+            world_bounds: view.world_bounds(),
             owner: view.owner(),
         }
     }
+
+    pub fn player_cell(&self, id: PlayerCellId) -> Option<&PlayerCell> {
+        self.player_indices.get(&id).map(|&i| &self.players[i])
+    }
+
+    pub fn food_cell(&self, id: FoodCellId) -> Option<&FoodCell> {
+        self.food_indices.get(&id).map(|&i| &self.food[i])
+    }
 }
 
+/// A single snapshot has nothing to animate between
+impl AnimatedView for ViewSnapshot {}
+
 impl GameView for ViewSnapshot {
     fn player_cells(&self) -> impl Iterator<Item = PlayerCell> {
         self.players.iter().cloned()
@@ -36,6 +79,14 @@ impl GameView for ViewSnapshot {
         self.food.iter().cloned()
     }
 
+    fn ejected_mass_cells(&self) -> impl Iterator<Item = EjectedMassCell> {
+        self.ejected_mass.iter().cloned()
+    }
+
+    fn virus_cells(&self) -> impl Iterator<Item = VirusCell> {
+        self.viruses.iter().cloned()
+    }
+
     fn player_infos(&self) -> impl Iterator<Item = &PlayerInfo> {
         self.info.iter()
     }
@@ -44,7 +95,15 @@ impl GameView for ViewSnapshot {
         self.view_area
     }
 
+    fn world_bounds(&self) -> Rect {
+        self.world_bounds
+    }
+
     fn owner(&self) -> PlayerId {
         self.owner
     }
+
+    fn leaderboard(&self) -> impl Iterator<Item = LeaderboardEntry> {
+        self.leaderboard.iter().copied()
+    }
 }
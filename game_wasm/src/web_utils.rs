@@ -50,3 +50,7 @@ pub fn now() -> f64 {
         .expect("could not find performance for window")
         .now()
 }
+
+pub fn log_error(message: &str) {
+    web_sys::console::error_1(&JsValue::from_str(message));
+}
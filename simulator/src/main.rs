@@ -0,0 +1,210 @@
+use std::{
+    env, fs,
+    path::Path,
+    process, thread,
+    time::{Duration, Instant},
+};
+
+use cell_game::{
+    ids::PlayerId,
+    server::{
+        game_config::GameConfig, game_server::GameServer, replay::Replay,
+        replay_player::ReplayPlayer,
+    },
+};
+use game_render::{scene_renderer::SceneRenderer, svg_backend::SvgBackend};
+use options::{Options, USAGE};
+
+mod options;
+
+/// The size in pixels of frames saved with `--frames`
+const FRAME_WIDTH: f64 = 1280.0;
+const FRAME_HEIGHT: f64 = 720.0;
+
+fn main() {
+    let options = match Options::parse(env::args().skip(1)) {
+        Ok(options) => options,
+        Err(e) => {
+            eprintln!("{}\n{}", e, USAGE);
+            process::exit(2);
+        }
+    };
+    if let Some(path) = &options.replay {
+        play_back(path);
+        return;
+    }
+    let config = match &options.config {
+        Some(path) => match GameConfig::from_file(path) {
+            Ok(config) => config,
+            Err(e) => {
+                eprintln!("{}", e);
+                process::exit(1);
+            }
+        },
+        None => GameConfig::default(),
+    };
+
+    print_settings(&options, &config);
+    let tick_duration = Duration::from_secs_f64(1.0 / config.tick_rate as f64);
+    let mut game = if options.record.is_some() {
+        GameServer::with_recording(config, options.seed)
+    } else {
+        GameServer::with_seed(config, options.seed)
+    };
+
+    let bots: Vec<PlayerId> = (0..options.bots)
+        .map(|i| game.connect_bot(format!("Bot {}", i), options.difficulty))
+        .collect();
+    if let Some(dir) = &options.frames {
+        if let Err(e) = fs::create_dir_all(dir) {
+            eprintln!("{}", e);
+            process::exit(1);
+        }
+    }
+    let mut scene = SceneRenderer::new();
+
+    let mut tick_times = Vec::with_capacity(options.ticks);
+    let mut next_tick = Instant::now();
+    for tick in 0..options.ticks {
+        let start = Instant::now();
+        game.tick();
+        tick_times.push(start.elapsed());
+
+        if let (Some(dir), Some(&bot)) = (&options.frames, bots.first()) {
+            if tick % options.frame_every.max(1) == 0 {
+                save_frame(
+                    &mut game,
+                    &mut scene,
+                    bot,
+                    &dir.join(format!("frame_{:06}.svg", tick)),
+                );
+            }
+        }
+
+        // Bots respawn as soon as they die, so that every bot plays the whole
+        // match
+        game.respawn_dead_players();
+
+        if options.real_time {
+            next_tick += tick_duration;
+            if let Some(wait) = next_tick.checked_duration_since(Instant::now()) {
+                thread::sleep(wait);
+            }
+        }
+    }
+
+    print_players(&game);
+    print_tick_times(tick_times);
+
+    if let (Some(path), Some(replay)) = (&options.record, game.replay()) {
+        match replay.save(path) {
+            Ok(()) => println!("replay saved to {}", path.display()),
+            Err(e) => {
+                eprintln!("{}", e);
+                process::exit(1);
+            }
+        }
+    }
+}
+
+/// Renders `player`'s view of the game to an SVG file
+fn save_frame(game: &mut GameServer, scene: &mut SceneRenderer, player: PlayerId, path: &Path) {
+    let Some(view) = game.player_view(player) else {
+        return;
+    };
+    let mut backend = SvgBackend::new(FRAME_WIDTH, FRAME_HEIGHT);
+    scene.render(&mut backend, &view);
+    if let Err(e) = backend.save(path) {
+        eprintln!("{}", e);
+        process::exit(1);
+    }
+}
+
+/// Plays a replay to the end and prints how each player finished, which
+/// should match the run it was recorded from
+fn play_back(path: &Path) {
+    let replay = match Replay::load(path) {
+        Ok(replay) => replay,
+        Err(e) => {
+            eprintln!("{}", e);
+            process::exit(1);
+        }
+    };
+    println!("replay:     {}", path.display());
+    println!("seed:       {}", replay.seed());
+    println!("ticks:      {}", replay.ticks());
+    println!();
+
+    let mut player = ReplayPlayer::new(replay);
+    while player.step() {}
+    print_players(player.game());
+}
+
+fn print_settings(options: &Options, config: &GameConfig) {
+    println!("seed:       {}", options.seed);
+    match &options.config {
+        Some(path) => println!("config:     {}", path.display()),
+        None => println!("config:     default"),
+    }
+    println!(
+        "arena:      {} x {}, {} food, {} viruses",
+        config.width, config.height, config.food_amount, config.virus_count
+    );
+    println!("bots:       {} ({:?})", options.bots, options.difficulty);
+    println!(
+        "ticks:      {} ({:.1}s of game time at {} ticks/s)",
+        options.ticks,
+        options.ticks as f64 / config.tick_rate as f64,
+        config.tick_rate
+    );
+    println!();
+}
+
+fn print_players(game: &GameServer) {
+    let mut results: Vec<_> = game
+        .player_infos()
+        .iter()
+        .map(|info| {
+            (
+                info.name(),
+                game.player_mass(info.id()),
+                game.player_stats(info.id()),
+            )
+        })
+        .collect();
+    results.sort_by(|a, b| b.1.total_cmp(&a.1));
+
+    println!(
+        "{:<10} {:>10} {:>10} {:>10} {:>7}",
+        "player", "mass", "food", "cells", "deaths"
+    );
+    let mut total_food = 0;
+    for (name, mass, stats) in results {
+        let stats = stats.unwrap_or_default();
+        total_food += stats.food_eaten;
+        println!(
+            "{:<10} {:>10.1} {:>10} {:>10} {:>7}",
+            name, mass, stats.food_eaten, stats.cells_eaten, stats.deaths
+        );
+    }
+    println!("total food eaten: {}", total_food);
+    println!();
+}
+
+fn print_tick_times(mut tick_times: Vec<Duration>) {
+    if tick_times.is_empty() {
+        return;
+    }
+    tick_times.sort();
+    let percentile = |p: f64| {
+        let i = ((tick_times.len() - 1) as f64 * p).round() as usize;
+        tick_times[i].as_secs_f64() * 1_000.0
+    };
+    println!(
+        "tick time (ms): p50 {:.3}  p90 {:.3}  p99 {:.3}  max {:.3}",
+        percentile(0.5),
+        percentile(0.9),
+        percentile(0.99),
+        percentile(1.0)
+    );
+}
@@ -0,0 +1,117 @@
+use std::{fmt, path::PathBuf};
+
+use cell_game::bot::difficulty::Difficulty;
+
+/// Settings for a simulation run, read from the command line
+pub struct Options {
+    pub bots: usize,
+    pub difficulty: Difficulty,
+    pub ticks: usize,
+    pub seed: u64,
+    /// A config file to load. The default config is used if this is `None`.
+    pub config: Option<PathBuf>,
+    /// Whether to tick at the config's tick rate instead of as fast as
+    /// possible
+    pub real_time: bool,
+    /// Where to save a replay of the run
+    pub record: Option<PathBuf>,
+    /// A replay to play back instead of simulating a new game
+    pub replay: Option<PathBuf>,
+    /// A directory to save SVG frames of the first bot's view to
+    pub frames: Option<PathBuf>,
+    /// How many ticks apart saved frames are
+    pub frame_every: usize,
+}
+
+pub const USAGE: &str =
+    "usage: simulator [--bots N] [--difficulty easy|normal|hard] [--ticks N] [--seed N] [--config FILE] [--real-time] [--record FILE] [--replay FILE] [--frames DIR] [--frame-every N]";
+
+impl Options {
+    pub fn parse(mut args: impl Iterator<Item = String>) -> Result<Self, OptionsError> {
+        let mut options = Self::default();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--bots" => options.bots = number(&arg, args.next())?,
+                "--difficulty" => {
+                    let value = args
+                        .next()
+                        .ok_or_else(|| OptionsError::MissingValue(arg.clone()))?;
+                    options.difficulty = match value.as_str() {
+                        "easy" => Difficulty::Easy,
+                        "normal" => Difficulty::Normal,
+                        "hard" => Difficulty::Hard,
+                        _ => return Err(OptionsError::UnknownDifficulty(value)),
+                    }
+                }
+                "--ticks" => options.ticks = number(&arg, args.next())?,
+                "--seed" => options.seed = number(&arg, args.next())?,
+                "--config" => {
+                    options.config =
+                        Some(args.next().ok_or(OptionsError::MissingValue(arg))?.into())
+                }
+                "--real-time" => options.real_time = true,
+                "--record" => {
+                    options.record =
+                        Some(args.next().ok_or(OptionsError::MissingValue(arg))?.into())
+                }
+                "--replay" => {
+                    options.replay =
+                        Some(args.next().ok_or(OptionsError::MissingValue(arg))?.into())
+                }
+                "--frames" => {
+                    options.frames =
+                        Some(args.next().ok_or(OptionsError::MissingValue(arg))?.into())
+                }
+                "--frame-every" => options.frame_every = number(&arg, args.next())?,
+                _ => return Err(OptionsError::Unknown(arg)),
+            }
+        }
+        Ok(options)
+    }
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            bots: 20,
+            difficulty: Difficulty::Normal,
+            ticks: 3_600,
+            seed: 0,
+            config: None,
+            real_time: false,
+            record: None,
+            replay: None,
+            frames: None,
+            frame_every: 60,
+        }
+    }
+}
+
+fn number<T: std::str::FromStr>(flag: &str, value: Option<String>) -> Result<T, OptionsError> {
+    let value = value.ok_or_else(|| OptionsError::MissingValue(flag.to_owned()))?;
+    value.parse().map_err(|_| OptionsError::NotANumber {
+        flag: flag.to_owned(),
+        value,
+    })
+}
+
+#[derive(Debug)]
+pub enum OptionsError {
+    Unknown(String),
+    MissingValue(String),
+    UnknownDifficulty(String),
+    NotANumber { flag: String, value: String },
+}
+
+impl fmt::Display for OptionsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Unknown(arg) => write!(f, "unknown argument {}", arg),
+            Self::MissingValue(flag) => write!(f, "{} needs a value", flag),
+            Self::UnknownDifficulty(value) => write!(f, "unknown difficulty {}", value),
+            Self::NotANumber { flag, value } => {
+                write!(f, "{} must be a number, not {}", flag, value)
+            }
+        }
+    }
+}
@@ -0,0 +1,77 @@
+use std::{
+    sync::mpsc::Receiver,
+    thread,
+    time::{Duration, Instant},
+};
+
+use cell_game::{
+    ids::PlayerId,
+    server::{game_config::GameConfig, game_event::GameEvent, game_server::GameServer},
+};
+
+use crate::socket_connection::{SocketConnection, SocketState};
+
+/// A socket that is waiting to be added to the game
+pub struct NewPlayer {
+    pub name: String,
+    pub connection: SocketConnection,
+    pub state: SocketState,
+}
+
+/// How long a player stays dead before they respawn
+const RESPAWN_DELAY: Duration = Duration::from_secs(3);
+
+/// Runs the game on the current thread at its tick rate forever. New players
+/// are added at the start of each tick, and players whose sockets closed are
+/// removed.
+pub fn run(config: GameConfig, new_players: Receiver<NewPlayer>) {
+    let tick_duration = Duration::from_secs_f64(1.0 / config.tick_rate as f64);
+    let respawn_ticks = (RESPAWN_DELAY.as_secs_f64() * config.tick_rate as f64) as u64;
+    let mut game = GameServer::with_config(config);
+
+    let mut sockets: Vec<(PlayerId, SocketState)> = Vec::new();
+    let mut respawns: Vec<(PlayerId, u64)> = Vec::new();
+    let mut tick: u64 = 0;
+    let mut next_tick = Instant::now();
+
+    loop {
+        for player in new_players.try_iter() {
+            let id = game.connect_player(player.name, Box::new(player.connection));
+            sockets.push((id, player.state));
+        }
+        sockets.retain(|(id, state)| {
+            if state.is_closed() {
+                game.disconnect_player(*id);
+                false
+            } else {
+                true
+            }
+        });
+        respawns.retain(|&(id, respawn_tick)| {
+            if respawn_tick <= tick {
+                game.respawn_player(id);
+                false
+            } else {
+                true
+            }
+        });
+
+        game.tick();
+        tick += 1;
+
+        for event in game.drain_events() {
+            match event {
+                GameEvent::PlayerDied { player, .. } => {
+                    respawns.push((player, tick + respawn_ticks))
+                }
+            }
+        }
+
+        next_tick += tick_duration;
+        match next_tick.checked_duration_since(Instant::now()) {
+            Some(wait) => thread::sleep(wait),
+            // Running behind, so don't try to catch up with a burst of ticks
+            None => next_tick = Instant::now(),
+        }
+    }
+}
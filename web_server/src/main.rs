@@ -1,13 +1,32 @@
+use cell_game::server::game_config::GameConfig;
+use game_loop::NewPlayer;
 use hyper::http::HeaderValue;
 use hyper::service::{make_service_fn, service_fn};
 use hyper::{header, Body, Method, Request, Response, Server, StatusCode};
-use std::{convert::Infallible, net::SocketAddr};
+use std::{
+    convert::Infallible,
+    env,
+    net::SocketAddr,
+    sync::{mpsc::Sender, Mutex},
+    thread,
+};
 use tokio::io::AsyncReadExt;
+use tokio_tungstenite::{
+    tungstenite::{handshake::derive_accept_key, protocol::Role},
+    WebSocketStream,
+};
 
-async fn handle(req: Request<Body>) -> Result<Response<Body>, Infallible> {
+mod game_loop;
+mod socket_connection;
+
+async fn handle(
+    req: Request<Body>,
+    new_players: &'static Mutex<Sender<NewPlayer>>,
+) -> Result<Response<Body>, Infallible> {
     let mut response = Response::new(Body::empty());
     println!("{} REQUEST:\t{}", req.method(), req.uri().path());
     match (req.method(), req.uri().path()) {
+        (&Method::GET, "/ws") => return Ok(upgrade_socket(req, new_players)),
         (&Method::GET, "/") => {
             static_file(
                 &mut response,
@@ -40,6 +59,42 @@ async fn handle(req: Request<Body>) -> Result<Response<Body>, Infallible> {
     Ok(response)
 }
 
+/// Accepts a WebSocket handshake and serves the socket once the connection is
+/// upgraded
+fn upgrade_socket(
+    mut req: Request<Body>,
+    new_players: &'static Mutex<Sender<NewPlayer>>,
+) -> Response<Body> {
+    let key = match req.headers().get(header::SEC_WEBSOCKET_KEY) {
+        Some(key) => derive_accept_key(key.as_bytes()),
+        None => {
+            let mut response = Response::new(Body::empty());
+            *response.status_mut() = StatusCode::BAD_REQUEST;
+            return response;
+        }
+    };
+
+    tokio::spawn(async move {
+        match hyper::upgrade::on(&mut req).await {
+            Ok(upgraded) => {
+                let socket = WebSocketStream::from_raw_socket(upgraded, Role::Server, None).await;
+                socket_connection::serve_socket(socket, new_players).await;
+            }
+            Err(e) => eprintln!("upgrade error: {}", e),
+        }
+    });
+
+    let mut response = Response::new(Body::empty());
+    *response.status_mut() = StatusCode::SWITCHING_PROTOCOLS;
+    let headers = response.headers_mut();
+    headers.insert(header::CONNECTION, HeaderValue::from_static("upgrade"));
+    headers.insert(header::UPGRADE, HeaderValue::from_static("websocket"));
+    if let Ok(key) = HeaderValue::from_str(&key) {
+        headers.insert(header::SEC_WEBSOCKET_ACCEPT, key);
+    }
+    response
+}
+
 async fn static_file(response: &mut Response<Body>, file_path: &str, content_type: HeaderValue) {
     let mut s = Vec::new();
     match tokio::fs::File::open(file_path).await {
@@ -60,7 +115,25 @@ async fn static_file(response: &mut Response<Body>, file_path: &str, content_typ
 async fn main() {
     let addr = SocketAddr::from(([127, 0, 0, 1], 8080));
 
-    let make_svc = make_service_fn(|_conn| async { Ok::<_, Infallible>(service_fn(handle)) });
+    // An optional config file can be passed as the first argument
+    let config = match env::args().nth(1) {
+        Some(path) => match GameConfig::from_file(&path) {
+            Ok(config) => config,
+            Err(e) => {
+                eprintln!("{}", e);
+                return;
+            }
+        },
+        None => GameConfig::default(),
+    };
+
+    let (sender, receiver) = std::sync::mpsc::channel();
+    thread::spawn(move || game_loop::run(config, receiver));
+    let new_players: &'static Mutex<Sender<NewPlayer>> = Box::leak(Box::new(Mutex::new(sender)));
+
+    let make_svc = make_service_fn(move |_conn| async move {
+        Ok::<_, Infallible>(service_fn(move |req| handle(req, new_players)))
+    });
 
     let server = Server::bind(&addr).serve(make_svc);
 
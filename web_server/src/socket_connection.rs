@@ -0,0 +1,247 @@
+use std::sync::{
+    atomic::{AtomicBool, AtomicU32, Ordering},
+    mpsc::Sender,
+    Arc, Mutex,
+};
+
+use cell_game::{
+    client_connection::{ClientConnection, PlayerInput},
+    game_view::GameView,
+    protocol::{
+        delta::ViewEncoder,
+        messages::{ClientMessage, ServerMessage},
+        PROTOCOL_VERSION,
+    },
+    server::server_view::ServerView,
+};
+use futures_util::{SinkExt, StreamExt};
+use hyper::upgrade::Upgraded;
+use tokio::sync::{
+    mpsc::{self, Receiver},
+    Notify,
+};
+use tokio_tungstenite::{tungstenite::Message, WebSocketStream};
+
+use crate::game_loop::NewPlayer;
+
+/// The state shared between a player's connection in the game loop and the
+/// task reading and writing their socket
+#[derive(Clone)]
+pub struct SocketState {
+    /// The most recent input read from the socket, and its sequence number.
+    /// Splits and ejects are kept until a tick uses them.
+    input: Arc<Mutex<(u32, PlayerInput)>>,
+    /// The most recent view frame the client acknowledged, or 0 if it hasn't
+    /// acknowledged any yet
+    acknowledged: Arc<AtomicU32>,
+    closed: Arc<AtomicBool>,
+    /// Wakes the socket's task when the connection is closed, even while it's
+    /// stuck writing to a client that stopped reading
+    close_signal: Arc<Notify>,
+}
+
+impl SocketState {
+    fn new() -> Self {
+        Self {
+            input: Arc::new(Mutex::new((0, PlayerInput::default()))),
+            acknowledged: Arc::new(AtomicU32::new(0)),
+            closed: Arc::new(AtomicBool::new(false)),
+            close_signal: Arc::new(Notify::new()),
+        }
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.closed.load(Ordering::Relaxed)
+    }
+
+    fn close(&self) {
+        self.closed.store(true, Ordering::Relaxed);
+        // Only the socket's task waits for this, and `notify_one` keeps the
+        // wakeup for it if it isn't waiting yet
+        self.close_signal.notify_one();
+    }
+}
+
+/// A player connected over a WebSocket. Views are queued to be sent by the
+/// socket's task, and the most recent input read from the socket is used for
+/// every tick until a new one arrives. Splits and ejects only happen on the
+/// first tick after the input asking for them arrives.
+pub struct SocketConnection {
+    state: SocketState,
+    outgoing: mpsc::Sender<Message>,
+    /// Whether the client has been told which player they control yet
+    welcomed: bool,
+    encoder: ViewEncoder,
+    /// The sequence number of the input returned on the last tick, which the
+    /// server has applied by the time it asks for the next view
+    applied_input: u32,
+}
+
+impl SocketConnection {
+    /// How many messages can be waiting to be written to the socket. Clients
+    /// that fall this far behind have stopped reading, so they are
+    /// disconnected rather than letting their views pile up.
+    const OUTGOING_QUEUE_SIZE: usize = 128;
+
+    /// Creates the game side of a connection along with the channel of
+    /// messages that should be written to the socket
+    fn new() -> (Self, SocketState, Receiver<Message>) {
+        let state = SocketState::new();
+        let (outgoing, outgoing_reader) = mpsc::channel(Self::OUTGOING_QUEUE_SIZE);
+        (
+            Self {
+                state: state.clone(),
+                outgoing,
+                welcomed: false,
+                encoder: ViewEncoder::new(),
+                applied_input: 0,
+            },
+            state,
+            outgoing_reader,
+        )
+    }
+
+    fn send(&mut self, message: ServerMessage) {
+        if !self.state.is_closed()
+            && self
+                .outgoing
+                .try_send(Message::Binary(message.encode()))
+                .is_err()
+        {
+            self.state.close();
+        }
+    }
+}
+
+/// The game dropping a connection means the player was removed, so the
+/// socket is closed too
+impl Drop for SocketConnection {
+    fn drop(&mut self) {
+        self.state.close();
+    }
+}
+
+impl<'a> ClientConnection<'a> for SocketConnection {
+    type V = ServerView<'a>;
+
+    fn on_tick(&mut self, view: Self::V) -> PlayerInput {
+        if !self.welcomed {
+            self.send(ServerMessage::Welcome {
+                version: PROTOCOL_VERSION,
+                player: view.owner(),
+            });
+            self.welcomed = true;
+        }
+        match self.state.acknowledged.load(Ordering::Relaxed) {
+            0 => {}
+            frame => self.encoder.acknowledge(frame),
+        }
+        let mut delta = self.encoder.encode(&view);
+        delta.input_ack = self.applied_input;
+        self.send(ServerMessage::View(Box::new(delta)));
+        let mut stored = self.state.input.lock().unwrap();
+        let (seq, input) = stored.clone();
+        stored.1.split = false;
+        stored.1.eject = false;
+        self.applied_input = seq;
+        input
+    }
+}
+
+/// Waits for the client's hello, then adds them to the game as a new player
+/// and serves them until either side closes. Clients that speak a different
+/// protocol version are told so and disconnected.
+pub async fn serve_socket(
+    mut socket: WebSocketStream<Upgraded>,
+    new_players: &Mutex<Sender<NewPlayer>>,
+) {
+    let name = loop {
+        match socket.next().await {
+            Some(Ok(Message::Binary(frame))) => match ClientMessage::decode(&frame) {
+                Ok(ClientMessage::Hello { version, name }) if version == PROTOCOL_VERSION => {
+                    break name
+                }
+                Ok(ClientMessage::Hello { .. }) => {
+                    let mismatch = ServerMessage::VersionMismatch {
+                        version: PROTOCOL_VERSION,
+                    };
+                    let _ = socket.send(Message::Binary(mismatch.encode())).await;
+                    let _ = socket.close(None).await;
+                    return;
+                }
+                Ok(_) => {}
+                Err(e) => eprintln!("could not decode hello: {}", e),
+            },
+            Some(Ok(Message::Close(_))) | Some(Err(_)) | None => return,
+            Some(Ok(_)) => {}
+        }
+    };
+
+    let (connection, state, outgoing) = SocketConnection::new();
+    let new_player = NewPlayer {
+        name,
+        connection,
+        state: state.clone(),
+    };
+    if new_players.lock().unwrap().send(new_player).is_ok() {
+        run_socket(socket, state, outgoing).await;
+    }
+}
+
+/// Forwards queued views to the socket and stores inputs read from it until
+/// either side closes. The socket is also dropped as soon as the connection is
+/// closed, such as when a client that stopped reading falls too far behind,
+/// even if a write to it is still waiting.
+async fn run_socket(
+    socket: WebSocketStream<Upgraded>,
+    state: SocketState,
+    mut outgoing: Receiver<Message>,
+) {
+    let (mut sink, mut stream) = socket.split();
+
+    let writer = async move {
+        while let Some(message) = outgoing.recv().await {
+            if sink.send(message).await.is_err() {
+                break;
+            }
+        }
+    };
+
+    let reader = async {
+        while let Some(message) = stream.next().await {
+            match message {
+                Ok(Message::Binary(frame)) => match ClientMessage::decode(&frame) {
+                    Ok(ClientMessage::Input { seq, input }) => {
+                        let mut stored = state.input.lock().unwrap();
+                        // Presses in inputs that arrive between ticks would be
+                        // lost if they were overwritten
+                        let split = stored.1.split || input.split;
+                        let eject = stored.1.eject || input.eject;
+                        *stored = (
+                            seq,
+                            PlayerInput {
+                                split,
+                                eject,
+                                ..input
+                            },
+                        );
+                    }
+                    Ok(ClientMessage::Ack(frame)) => {
+                        state.acknowledged.fetch_max(frame, Ordering::Relaxed);
+                    }
+                    Ok(ClientMessage::Hello { .. }) => {}
+                    Err(e) => eprintln!("could not decode input: {}", e),
+                },
+                Ok(Message::Close(_)) | Err(_) => break,
+                Ok(_) => {}
+            }
+        }
+    };
+
+    tokio::select! {
+        _ = writer => {}
+        _ = reader => {}
+        _ = state.close_signal.notified() => {}
+    }
+    state.close();
+}